@@ -0,0 +1,127 @@
+//! Compare spawning and despawning projectiles with reusing them from a pool.
+//!
+//! Run with `cargo bench --bench projectiles`.
+
+use bevy::prelude::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use spaceship::pool::{Pool, PoolPlugin, Pooled};
+use spaceship::weapon::{despawn_projectile, Projectile, ProjectileBundle, ProjectileKind};
+
+/// Frames each projectile lives, so there are this many frames' worth of
+/// projectiles on screen at once.
+const PROJECTILE_FRAMES: u32 = 20;
+/// Distance a projectile moves each frame.
+const SPEED: f32 = 5.0;
+
+/// Projectiles fired per frame, as a resource so the systems can see it.
+#[derive(Resource)]
+struct Rate(usize);
+
+fn projectile(n: usize) -> ProjectileBundle {
+    let velocity_vector = Vec2::from_angle(n as f32 * 0.1) * SPEED;
+    ProjectileBundle {
+        projectile: Projectile::new(velocity_vector, false, ProjectileKind::Torpedo),
+        sprite: Sprite::default(),
+        transform: Transform::default(),
+    }
+}
+
+/// Move the projectiles a frame's worth, and return the ones that have
+/// gone far enough.
+fn move_projectiles(
+    mut query: Query<(&Projectile, &mut Transform, Option<&Pooled>, Entity)>,
+) -> Vec<Entity> {
+    let range = SPEED * (PROJECTILE_FRAMES as f32 - 0.5);
+    let mut expired = Vec::new();
+    for (projectile, mut transform, pooled, entity) in &mut query {
+        if pooled.is_some_and(|pooled| !pooled.active()) {
+            continue;
+        }
+        transform.translation += projectile.velocity_vector.extend(0.0);
+        if transform.translation.length() > range {
+            expired.push(entity);
+        }
+    }
+    expired
+}
+
+fn spawn_projectiles(mut commands: Commands, rate: Res<Rate>) {
+    for n in 0..rate.0 {
+        commands.spawn(projectile(n));
+    }
+}
+
+fn despawn_projectiles(In(expired): In<Vec<Entity>>, mut commands: Commands) {
+    for entity in expired {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_pooled_projectiles(
+    mut commands: Commands,
+    mut pool: ResMut<Pool<Projectile>>,
+    rate: Res<Rate>,
+) {
+    for n in 0..rate.0 {
+        pool.spawn(&mut commands, projectile(n));
+    }
+}
+
+fn release_projectiles(In(expired): In<Vec<Entity>>, mut commands: Commands) {
+    for entity in expired {
+        despawn_projectile(&mut commands, entity);
+    }
+}
+
+fn spawn_despawn_app(rate: usize) -> App {
+    let mut app = App::new();
+    app.insert_resource(Rate(rate)).add_systems(
+        Update,
+        (
+            spawn_projectiles,
+            move_projectiles.pipe(despawn_projectiles),
+        )
+            .chain(),
+    );
+    app
+}
+
+fn pooled_app(rate: usize) -> App {
+    let mut app = App::new();
+    app.insert_resource(Rate(rate))
+        .add_plugins(PoolPlugin::<Projectile>::new(
+            rate * PROJECTILE_FRAMES as usize,
+        ))
+        .add_systems(
+            Update,
+            (
+                spawn_pooled_projectiles,
+                move_projectiles.pipe(release_projectiles),
+            )
+                .chain(),
+        );
+    app
+}
+
+fn projectiles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("projectiles");
+    for rate in [50, 200, 500] {
+        let active = rate * PROJECTILE_FRAMES as usize;
+        let apps = [
+            ("spawn_despawn", spawn_despawn_app(rate)),
+            ("pool", pooled_app(rate)),
+        ];
+        for (name, mut app) in apps {
+            // Fill the screen before measuring.
+            for _ in 0..2 * PROJECTILE_FRAMES {
+                app.update();
+            }
+            group.bench_function(BenchmarkId::new(name, active), |b| b.iter(|| app.update()));
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, projectiles);
+criterion_main!(benches);
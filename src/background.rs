@@ -1,42 +1,344 @@
-use bevy::math::vec3;
+use std::borrow::Cow;
+use std::f32::consts::TAU;
+
+use bevy::math::{vec2, vec3};
 use bevy::prelude::*;
 
-const MOVEMENT_RATE: f32 = 40.0;
-// FIXME: determine this at runtime.
-const BG_REPEAT_HEIGHT: f32 = 800.0;
+/// Depth of the farthest background layer; nearer layers are stacked above it.
+const BG_DEPTH: f32 = -100.0;
+
+/// One scrolling layer of the background.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BgLayer {
+    /// What this layer is made of.
+    pub source: BgSource,
+    /// Scroll speed in pixels per second.
+    pub speed: f32,
+    /// Tint for the layer. Use a partial alpha to let farther layers show through.
+    pub color: Color,
+    /// Mirror the image, so one image can be reused for several layers.
+    pub flip_x: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BgSource {
+    /// Tile an image asset.
+    Image(&'static str),
+    /// Generate stars at random positions.
+    Starfield(Starfield),
+}
+
+/// Settings for a procedurally generated starfield.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Starfield {
+    /// The same seed always generates the same stars.
+    pub seed: u64,
+    /// Number of stars per screen.
+    pub stars: usize,
+    /// Smallest star size, in pixels.
+    pub min_size: f32,
+    /// Largest star size, in pixels.
+    pub max_size: f32,
+    /// How much star brightness varies over time, from 0.0 to 1.0.
+    pub twinkle: f32,
+}
+
+/// The background used before the first level starts.
+pub const DEFAULT_BACKGROUND: &[BgLayer] = &[BgLayer {
+    source: BgSource::Image("stars1.png"),
+    speed: 40.0,
+    color: Color::WHITE,
+    flip_x: false,
+}];
+
+/// Generate a background that contains only starfields.
+///
+/// Each seed produces different star positions and a different tint.
+pub fn starfield_background(seed: u64) -> Vec<BgLayer> {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let mut tint = || 0.7 + 0.3 * rng.f32();
+    let far_color = Color::srgb(tint(), tint(), tint());
+    let near_color = Color::srgb(tint(), tint(), tint());
+
+    vec![
+        BgLayer {
+            source: BgSource::Starfield(Starfield {
+                seed,
+                stars: 150,
+                min_size: 1.0,
+                max_size: 2.0,
+                twinkle: 0.6,
+            }),
+            speed: 20.0,
+            color: far_color,
+            flip_x: false,
+        },
+        BgLayer {
+            source: BgSource::Starfield(Starfield {
+                seed: seed.wrapping_add(1),
+                stars: 40,
+                min_size: 2.0,
+                max_size: 3.0,
+                twinkle: 0.3,
+            }),
+            speed: 60.0,
+            color: near_color,
+            flip_x: false,
+        },
+    ]
+}
 
 pub struct BgPlugin;
 
 impl Plugin for BgPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, init_background)
-            .add_systems(Update, move_background);
+        app.add_event::<BackgroundResetEvent>()
+            .insert_resource(CurrentBackground(Cow::Borrowed(DEFAULT_BACKGROUND)))
+            .add_systems(Startup, init_background)
+            .add_systems(
+                Update,
+                (reset_background, tile_background, move_background).chain(),
+            )
+            .add_systems(Update, twinkle_stars);
     }
 }
 
+/// The layers that are currently onscreen.
+#[derive(Resource)]
+struct CurrentBackground(Cow<'static, [BgLayer]>);
+
+/// A background layer.
+///
+/// The layer entity is the one that scrolls; the tiles are its children,
+/// and are spawned once the tile size is known.
 #[derive(Component)]
-struct Background;
+struct Background {
+    layer: BgLayer,
+    /// The layer speed, after scaling.
+    speed: f32,
+    /// The image to tile, for image layers.
+    image: Option<Handle<Image>>,
+    /// The height of one tile, once the layer has been tiled.
+    tile_height: Option<f32>,
+}
+
+/// Make a star fade in and out.
+#[derive(Clone, Copy, Component)]
+struct Twinkle {
+    /// Brightness when fully lit.
+    alpha: f32,
+    /// Fraction of the brightness that fades.
+    amount: f32,
+    /// Twinkle cycles per second.
+    rate: f32,
+    /// Offset into the cycle, so the stars don't all twinkle together.
+    phase: f32,
+}
+
+/// Replace the background layers.
+#[derive(Event)]
+pub struct BackgroundResetEvent {
+    pub layers: Cow<'static, [BgLayer]>,
+    /// Multiplier for every layer's scroll speed.
+    pub speed_scale: f32,
+}
 
-/// Spawn a sprite with the background image
+/// Spawn the sprites for the default background.
 fn init_background(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let image = asset_server.load("stars1.png");
-    let transform = Transform::from_translation(vec3(0.0, BG_REPEAT_HEIGHT / 2.0, -100.0));
+    spawn_layers(&mut commands, &asset_server, DEFAULT_BACKGROUND, 1.0);
+}
+
+/// Spawn one entity per layer, farthest layer first.
+fn spawn_layers(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    layers: &[BgLayer],
+    speed_scale: f32,
+) {
+    for (index, layer) in layers.iter().enumerate() {
+        let image = match &layer.source {
+            BgSource::Image(path) => Some(asset_server.load(*path)),
+            BgSource::Starfield(_) => None,
+        };
+        let z = BG_DEPTH + index as f32;
+        let background = Background {
+            layer: layer.clone(),
+            speed: layer.speed * speed_scale,
+            image,
+            tile_height: None,
+        };
+
+        commands.spawn((
+            background,
+            Transform::from_translation(vec3(0.0, 0.0, z)),
+            Visibility::default(),
+        ));
+    }
+}
+
+/// Cover the visible area with copies of each layer.
+///
+/// Image layers wait until the image is loaded, so that any image size will work.
+/// Starfield layers are generated at exactly the size of the visible area.
+fn tile_background(
+    mut commands: Commands,
+    mut query: Query<(&mut Background, Entity)>,
+    camera: Query<&Projection>,
+    images: Res<Assets<Image>>,
+) {
+    let Ok(Projection::Orthographic(projection)) = camera.get_single() else {
+        return;
+    };
+    let view = projection.area.size();
+    if view.x <= 0.0 || view.y <= 0.0 {
+        // The projection hasn't been computed yet.
+        return;
+    }
 
-    commands.spawn((Sprite::from_image(image), transform, Background));
+    for (mut background, entity) in &mut query {
+        if background.tile_height.is_some() {
+            continue;
+        }
+
+        let tile_height = match (&background.layer.source, &background.image) {
+            (BgSource::Image(_), Some(handle)) => {
+                let Some(image) = images.get(handle) else {
+                    continue;
+                };
+                let sprite = Sprite {
+                    image: handle.clone(),
+                    color: background.layer.color,
+                    flip_x: background.layer.flip_x,
+                    ..default()
+                };
+                let tile = image.size_f32();
+                tile_image(&mut commands, entity, sprite, tile, view);
+                tile.y
+            }
+            (BgSource::Starfield(starfield), _) => {
+                let color = background.layer.color;
+                tile_starfield(&mut commands, entity, starfield, color, view);
+                view.y
+            }
+            (BgSource::Image(_), None) => unreachable!("image layer without an image"),
+        };
+        background.tile_height = Some(tile_height);
+    }
 }
 
-/// Slowly move the background image
-fn move_background(time: Res<Time>, mut query: Query<&mut Transform, With<Background>>) {
-    let mut transform = query.single_mut();
-    let mut new_y = transform.translation.y - MOVEMENT_RATE * time.delta_secs();
+/// Spawn enough copies of an image to cover the visible area.
+fn tile_image(commands: &mut Commands, entity: Entity, sprite: Sprite, tile: Vec2, view: Vec2) {
+    // One extra row, because the layer scrolls by up to one tile height
+    // before it wraps around.
+    let rows = (view.y / tile.y).ceil() as usize + 1;
+    // An odd number of columns, so the middle one can be centered.
+    let columns = (view.x / tile.x).ceil() as usize | 1;
+
+    commands.entity(entity).with_children(|parent| {
+        for row in 0..rows {
+            let y = (tile.y - view.y) / 2.0 + row as f32 * tile.y;
+            for column in 0..columns {
+                let x = (column as f32 - (columns / 2) as f32) * tile.x;
+                parent.spawn((sprite.clone(), Transform::from_translation(vec3(x, y, 0.0))));
+            }
+        }
+    });
+}
+
+/// Spawn a screen-sized field of stars, twice.
+///
+/// The second copy sits directly above the first, so the layer can wrap
+/// the same way an image layer does.
+fn tile_starfield(
+    commands: &mut Commands,
+    entity: Entity,
+    starfield: &Starfield,
+    color: Color,
+    view: Vec2,
+) {
+    let mut rng = fastrand::Rng::with_seed(starfield.seed);
+
+    commands.entity(entity).with_children(|parent| {
+        for _ in 0..starfield.stars {
+            let x = (rng.f32() - 0.5) * view.x;
+            let y = (rng.f32() - 0.5) * view.y;
+            let size = starfield.min_size + rng.f32() * (starfield.max_size - starfield.min_size);
+            let twinkle = Twinkle {
+                alpha: 0.4 + 0.6 * rng.f32(),
+                amount: starfield.twinkle * rng.f32(),
+                rate: 0.2 + rng.f32(),
+                phase: rng.f32() * TAU,
+            };
+
+            for row in 0..2 {
+                let sprite = Sprite {
+                    color: color.with_alpha(twinkle.alpha),
+                    custom_size: Some(vec2(size, size)),
+                    ..default()
+                };
+                let translation = vec3(x, y + row as f32 * view.y, 0.0);
+                parent.spawn((sprite, Transform::from_translation(translation), twinkle));
+            }
+        }
+    });
+}
 
-    // The background image is tiled 2x so the top half and bottom half are identical.
-    // The background will scroll from +BG_REPEAT_HEIGHT/2 TO -BG_REPEAT_HEIGHT/2
-    // at which point we will reset it by exactly BG_REPEAT_HEIGHT, so the change
-    // is invisible.
+/// Swap in a new set of layers, unless they are already onscreen.
+fn reset_background(
+    mut commands: Commands,
+    mut event: EventReader<BackgroundResetEvent>,
+    mut current: ResMut<CurrentBackground>,
+    asset_server: Res<AssetServer>,
+    mut query: Query<(&mut Background, Entity)>,
+) {
+    let Some(BackgroundResetEvent {
+        layers,
+        speed_scale,
+    }) = event.read().last()
+    else {
+        return;
+    };
+
+    if current.0 == *layers {
+        // Same layers; just update the speed.
+        for (mut background, _) in &mut query {
+            background.speed = background.layer.speed * speed_scale;
+        }
+        return;
+    }
+
+    for (_, entity) in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+    spawn_layers(&mut commands, &asset_server, layers, *speed_scale);
+    current.0 = layers.clone();
+}
+
+/// Slowly move the background layers, each at its own speed.
+fn move_background(time: Res<Time>, mut query: Query<(&mut Transform, &Background)>) {
+    for (mut transform, background) in &mut query {
+        let Some(tile_height) = background.tile_height else {
+            continue;
+        };
+        let mut new_y = transform.translation.y - background.speed * time.delta_secs();
+
+        // The tiles are identical, so once the layer has scrolled down by
+        // a full tile height we can move it back up by exactly that much,
+        // and the change is invisible.
+
+        if new_y < -tile_height {
+            new_y += tile_height;
+        }
+        transform.translation.y = new_y;
+    }
+}
 
-    if new_y < -BG_REPEAT_HEIGHT / 2.0 {
-        new_y += BG_REPEAT_HEIGHT;
+/// Vary the brightness of each star.
+fn twinkle_stars(time: Res<Time>, mut query: Query<(&mut Sprite, &Twinkle)>) {
+    let t = time.elapsed_secs();
+    for (mut sprite, twinkle) in &mut query {
+        let wave = 0.5 + 0.5 * (t * twinkle.rate * TAU + twinkle.phase).sin();
+        let alpha = twinkle.alpha * (1.0 - twinkle.amount * wave);
+        sprite.color.set_alpha(alpha);
     }
-    transform.translation.y = new_y;
 }
@@ -0,0 +1,146 @@
+//! The smart bomb, which clears the screen.
+
+use bevy::audio::{PlaybackMode, Volume};
+use bevy::prelude::*;
+
+use crate::collide::{check_player_collisions, Dying, EnemyDeathEvent};
+use crate::elite::Elite;
+use crate::enemy::{Enemy, SpawnerResetEvent};
+use crate::player::{Invulnerable, Player};
+use crate::pool::Pooled;
+use crate::weapon::{despawn_projectile, Projectile};
+use crate::GameState;
+
+/// Bombs available at the start of each level.
+const BOMBS_PER_LEVEL: u32 = 3;
+/// Seconds of invulnerability after a bomb.
+const BOMB_INVULNERABLE_SECONDS: f32 = 1.0;
+/// Seconds for the screen flash to fade out.
+const FLASH_SECONDS: f32 = 0.4;
+
+pub struct BombPlugin;
+
+impl Plugin for BombPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SmartBombEvent>()
+            .insert_resource(SmartBombs::default())
+            .add_systems(Startup, BombAssets::load)
+            .add_systems(
+                Update,
+                (detonate_bomb.before(check_player_collisions), restock_bombs)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, fade_flash);
+    }
+}
+
+#[derive(Resource)]
+struct BombAssets {
+    explosion_sound: Handle<AudioSource>,
+}
+
+impl BombAssets {
+    fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let explosion_sound = asset_server.load("bomb.wav");
+        commands.insert_resource(BombAssets { explosion_sound });
+    }
+}
+
+/// Bombs the player has left.
+#[derive(Default, Resource)]
+pub struct SmartBombs(pub u32);
+
+/// The player set off a bomb.
+#[derive(Event)]
+pub struct SmartBombEvent(pub Entity);
+
+/// A white flash covering the screen.
+#[derive(Component)]
+struct ScreenFlash(Timer);
+
+#[expect(clippy::too_many_arguments)]
+fn detonate_bomb(
+    mut commands: Commands,
+    mut event: EventReader<SmartBombEvent>,
+    mut bombs: ResMut<SmartBombs>,
+    assets: Res<BombAssets>,
+    projectiles: Query<(&Projectile, &Pooled, Entity)>,
+    mut enemies: Query<(&Transform, &Enemy, Option<&mut Elite>, Entity), Without<Dying>>,
+    players: Query<(), With<Player>>,
+    mut enemy_death_sender: EventWriter<EnemyDeathEvent>,
+) {
+    // Holding the button down shouldn't use up more than one bomb.
+    let Some(SmartBombEvent(player)) = event.read().last() else {
+        return;
+    };
+    if bombs.0 == 0 || players.get(*player).is_err() {
+        return;
+    }
+    bombs.0 -= 1;
+    info!("smart bomb ({} left)", bombs.0);
+
+    for (projectile, pooled, entity) in &projectiles {
+        if pooled.active() && !projectile.player {
+            despawn_projectile(&mut commands, entity);
+        }
+    }
+
+    for (transform, enemy, mut elite, entity) in &mut enemies {
+        let loc = transform.translation;
+        let onscreen = loc.x.abs() < 200.0 && loc.y.abs() < 400.0;
+        if !onscreen {
+            continue;
+        }
+        if let Some(elite) = &mut elite {
+            if elite.absorb_hit(1) {
+                continue;
+            }
+        }
+        commands.entity(entity).try_insert(Dying);
+        enemy_death_sender.send(EnemyDeathEvent::new(
+            entity,
+            transform,
+            enemy,
+            elite.as_deref(),
+        ));
+    }
+
+    commands
+        .entity(*player)
+        .insert(Invulnerable::new(BOMB_INVULNERABLE_SECONDS));
+    commands.spawn((
+        Sprite::from_color(Color::WHITE, Vec2::new(400.0, 800.0)),
+        Transform::from_xyz(0.0, 0.0, 50.0),
+        ScreenFlash(Timer::from_seconds(FLASH_SECONDS, TimerMode::Once)),
+    ));
+    commands.spawn((
+        AudioPlayer(assets.explosion_sound.clone_weak()),
+        PlaybackSettings {
+            mode: PlaybackMode::Despawn,
+            volume: Volume::new(0.8),
+            ..default()
+        },
+    ));
+}
+
+/// Fade out the screen flash.
+fn fade_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(&mut ScreenFlash, &mut Sprite, Entity)>,
+) {
+    for (mut flash, mut sprite, entity) in &mut query {
+        flash.0.tick(time.delta());
+        sprite.color.set_alpha(0.8 * flash.0.fraction_remaining());
+        if flash.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Top up the bombs when a level starts.
+fn restock_bombs(mut event: EventReader<SpawnerResetEvent>, mut bombs: ResMut<SmartBombs>) {
+    if event.read().last().is_some() {
+        bombs.0 = bombs.0.max(BOMBS_PER_LEVEL);
+    }
+}
@@ -3,29 +3,62 @@ use bevy::math::bounding::{Aabb2d, IntersectsVolume};
 use bevy::math::vec2;
 use bevy::prelude::*;
 
+use crate::elite::{Elite, Modifier};
 use crate::enemy::Enemy;
-use crate::level::LevelRestartEvent;
-use crate::player::Player;
+use crate::health::Health;
+use crate::level::{CurrentLevel, LevelRestartEvent};
+use crate::player::{Invulnerable, Player};
+use crate::pool::Pooled;
 use crate::ui::{GameOverEvent, PlayerLives};
-use crate::weapon::Projectile;
+use crate::weapon::{despawn_projectile, Projectile, ProjectileKind};
 use crate::GameState;
 
 // FIXME: these hitboxes kind of suck.
 // The players and enemies are triangular, and these are rectangles.
-const PLAYER_HITBOX: Vec2 = vec2(15.0, 12.5);
+pub const PLAYER_HITBOX: Vec2 = vec2(15.0, 12.5);
 const ENEMY_HITBOX: Vec2 = vec2(15.0, 12.5);
+const BOMBER_HITBOX: Vec2 = vec2(20.0, 16.0);
+const CARRIER_HITBOX: Vec2 = vec2(32.0, 26.0);
 const PROJECTILE_HITBOX: Vec2 = vec2(1.0, 2.0);
+const BOMB_HITBOX: Vec2 = vec2(6.0, 6.0);
+const CHARGED_HITBOX: Vec2 = vec2(6.0, 10.0);
+const MISSILE_HITBOX: Vec2 = vec2(4.0, 8.0);
+/// Damage from flying into an enemy ship.
+const RAM_DAMAGE: f32 = 50.0;
+/// Seconds of invulnerability after surviving a hit.
+const HIT_INVULNERABLE_SECONDS: f32 = 0.75;
+
+fn enemy_hitbox(enemy: &Enemy) -> Vec2 {
+    match enemy {
+        Enemy::Bomber { .. } => BOMBER_HITBOX,
+        Enemy::Carrier { .. } => CARRIER_HITBOX,
+        _ => ENEMY_HITBOX,
+    }
+}
+
+pub fn projectile_hitbox(projectile: &Projectile) -> Vec2 {
+    match projectile.kind {
+        ProjectileKind::Torpedo => PROJECTILE_HITBOX,
+        ProjectileKind::Bomb => BOMB_HITBOX,
+        ProjectileKind::Charged => CHARGED_HITBOX,
+        ProjectileKind::Missile => MISSILE_HITBOX,
+    }
+}
 
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<PlayerDeathEvent>()
+        app.add_event::<PlayerHitEvent>()
+            .add_event::<PlayerDeathEvent>()
             .add_event::<EnemyDeathEvent>()
             .add_systems(Startup, CollisionAssets::load)
             .add_systems(
                 Update,
+                // Chained so that an enemy marked `Dying` by the first isn't
+                // destroyed again by the second.
                 (check_player_collisions, check_enemy_collisions)
+                    .chain()
                     .run_if(in_state(GameState::Playing)),
             )
             // Make sure the player death runs in the same frame as the
@@ -33,7 +66,8 @@ impl Plugin for CollisionPlugin {
             // detected twice.
             .add_systems(
                 Update,
-                player_death
+                (player_hit, player_death)
+                    .chain()
                     .after(check_player_collisions)
                     .run_if(in_state(GameState::Playing)),
             )
@@ -96,58 +130,113 @@ impl CollisionAssets {
     }
 }
 
+/// The player was hit, for this much damage.
 #[derive(Event)]
-pub struct PlayerDeathEvent(Entity);
+pub struct PlayerHitEvent {
+    pub player: Entity,
+    pub damage: f32,
+}
 
 #[derive(Event)]
-pub struct EnemyDeathEvent(Entity);
+pub struct PlayerDeathEvent(pub Entity);
+
+#[derive(Event)]
+pub struct EnemyDeathEvent {
+    pub entity: Entity,
+    /// Points for destroying this enemy.
+    pub points: u32,
+    pub position: Vec3,
+    /// The elite modifier, if any.
+    pub modifier: Option<Modifier>,
+}
+
+/// An enemy that has been destroyed or has escaped, and will be despawned at
+/// the end of the frame.
+///
+/// Whatever sends an `EnemyDeathEvent` or `EnemyEscapeEvent` marks the enemy,
+/// and skips marked enemies, so each one only leaves the level once.
+#[derive(Component)]
+pub struct Dying;
+
+impl EnemyDeathEvent {
+    pub fn new(
+        entity: Entity,
+        transform: &Transform,
+        enemy: &Enemy,
+        elite: Option<&Elite>,
+    ) -> Self {
+        let modifier = elite.map(|elite| elite.modifier);
+        Self {
+            entity,
+            points: enemy.points() + modifier.map_or(0, Modifier::points),
+            position: transform.translation,
+            modifier,
+        }
+    }
+}
 
 /// Check if the player has collided with something.
 ///
 /// Collisions that we act on:
 /// - enemy shots hitting players
-/// - player ship hitting enemy ship (destroys both)
+/// - player ship hitting enemy ship (destroys the enemy, damages the player)
 ///
 /// Collisions that are ignored:
+/// - anything hitting an invulnerable player
 /// - player shots hitting players
 /// - projectiles hitting other projectiles
 ///
-fn check_player_collisions(
+pub fn check_player_collisions(
     mut commands: Commands,
-    player_query: Query<(&Transform, Entity), With<Player>>,
-    projectiles_query: Query<(&Transform, &Projectile, Entity)>,
-    enemies_query: Query<(&Transform, Entity), With<Enemy>>,
-    mut player_death_sender: EventWriter<PlayerDeathEvent>,
+    player_query: Query<(&Transform, Entity, Has<Invulnerable>), With<Player>>,
+    projectiles_query: Query<(&Transform, &Projectile, &Pooled, Entity)>,
+    enemies_query: Query<(&Transform, &Enemy, Option<&Elite>, Entity), Without<Dying>>,
+    mut player_hit_sender: EventWriter<PlayerHitEvent>,
     mut enemy_death_sender: EventWriter<EnemyDeathEvent>,
 ) {
-    let Ok((player_transform, player_entity)) = player_query.get_single() else {
+    let Ok((player_transform, player_entity, invulnerable)) = player_query.get_single() else {
         return;
     };
+    if invulnerable {
+        return;
+    }
 
     // check for player-projectile collisions
-    for (projectile_transform, projectile, proj_entity) in &projectiles_query {
-        if projectile.player {
+    for (projectile_transform, projectile, pooled, proj_entity) in &projectiles_query {
+        if !pooled.active() || projectile.player {
             continue;
         }
 
         let projectile_box = Aabb2d::new(
             projectile_transform.translation.truncate(),
-            PROJECTILE_HITBOX,
+            projectile_hitbox(projectile),
         );
         let player_box = Aabb2d::new(player_transform.translation.truncate(), PLAYER_HITBOX);
         if projectile_box.intersects(&player_box) {
-            player_death_sender.send(PlayerDeathEvent(player_entity));
-            commands.entity(proj_entity).despawn();
+            player_hit_sender.send(PlayerHitEvent {
+                player: player_entity,
+                damage: projectile.kind.damage(),
+            });
+            despawn_projectile(&mut commands, proj_entity);
         }
     }
 
     // check for player-enemy collisions
-    for (enemy_transform, enemy_entity) in &enemies_query {
-        let enemy_box = Aabb2d::new(enemy_transform.translation.truncate(), ENEMY_HITBOX);
+    for (enemy_transform, enemy, elite, enemy_entity) in &enemies_query {
+        let enemy_box = Aabb2d::new(enemy_transform.translation.truncate(), enemy_hitbox(enemy));
         let player_box = Aabb2d::new(player_transform.translation.truncate(), PLAYER_HITBOX);
         if enemy_box.intersects(&player_box) {
-            player_death_sender.send(PlayerDeathEvent(player_entity));
-            enemy_death_sender.send(EnemyDeathEvent(enemy_entity));
+            player_hit_sender.send(PlayerHitEvent {
+                player: player_entity,
+                damage: RAM_DAMAGE,
+            });
+            commands.entity(enemy_entity).try_insert(Dying);
+            enemy_death_sender.send(EnemyDeathEvent::new(
+                enemy_entity,
+                enemy_transform,
+                enemy,
+                elite,
+            ));
         }
     }
 }
@@ -155,38 +244,101 @@ fn check_player_collisions(
 /// Check if the enemies have collided with something.
 ///
 /// Collisions that we act on:
-/// - player shots hitting enemies
+/// - player shots hitting enemies (elites may take several hits)
 ///
 /// Collisions that are ignored:
 /// - enemy ships hitting each other
 /// - enemy shots hitting enemies
 /// - projectiles hitting other projectiles
 ///
-fn check_enemy_collisions(
+pub fn check_enemy_collisions(
     mut commands: Commands,
-    projectiles_query: Query<(&Transform, &Projectile, Entity)>,
-    enemies_query: Query<(&Transform, Entity), With<Enemy>>,
+    mut projectiles_query: Query<(&Transform, &mut Projectile, &Pooled, Entity)>,
+    mut enemies_query: Query<(&Transform, &Enemy, Option<&mut Elite>, Entity), Without<Dying>>,
     mut enemy_death_sender: EventWriter<EnemyDeathEvent>,
+    mut current_level: ResMut<CurrentLevel>,
 ) {
-    for (enemy_transform, enemy_entity) in &enemies_query {
-        for (projectile_transform, projectile, proj_entity) in &projectiles_query {
-            if !projectile.player {
+    for (enemy_transform, enemy, mut elite, enemy_entity) in &mut enemies_query {
+        for (projectile_transform, mut projectile, pooled, proj_entity) in &mut projectiles_query {
+            // Used-up projectiles aren't released until the end of the frame.
+            if !pooled.active()
+                || !projectile.player
+                || projectile.pierce == 0
+                || projectile.struck.contains(&enemy_entity)
+            {
                 continue;
             }
 
             let projectile_box = Aabb2d::new(
                 projectile_transform.translation.truncate(),
-                PROJECTILE_HITBOX,
+                projectile_hitbox(&projectile),
             );
-            let enemy_box = Aabb2d::new(enemy_transform.translation.truncate(), ENEMY_HITBOX);
+            let enemy_box =
+                Aabb2d::new(enemy_transform.translation.truncate(), enemy_hitbox(enemy));
             if projectile_box.intersects(&enemy_box) {
-                enemy_death_sender.send(EnemyDeathEvent(enemy_entity));
-                commands.entity(proj_entity).despawn();
+                projectile.pierce = projectile.pierce.saturating_sub(1);
+                if projectile.pierce > 0 {
+                    projectile.struck.push(enemy_entity);
+                } else {
+                    despawn_projectile(&mut commands, proj_entity);
+                }
+                current_level.stats.hits += 1;
+                if let Some(elite) = &mut elite {
+                    if elite.absorb_hit(projectile.damage) {
+                        continue;
+                    }
+                }
+                commands.entity(enemy_entity).try_insert(Dying);
+                enemy_death_sender.send(EnemyDeathEvent::new(
+                    enemy_entity,
+                    enemy_transform,
+                    enemy,
+                    elite.as_deref(),
+                ));
+                // Don't use up any more projectiles on this enemy.
+                break;
             }
         }
     }
 }
 
+/// Decide whether a hit destroys the player.
+///
+/// With arcade rules every hit is fatal; otherwise the damage goes to the
+/// shield and hull.
+fn player_hit(
+    mut commands: Commands,
+    mut event: EventReader<PlayerHitEvent>,
+    mut players: Query<Option<&mut Health>, With<Player>>,
+    mut player_death_sender: EventWriter<PlayerDeathEvent>,
+) {
+    let mut hits: Vec<(Entity, f32)> = Vec::new();
+    for event in event.read() {
+        match hits.iter_mut().find(|(player, _)| *player == event.player) {
+            Some((_, damage)) => *damage += event.damage,
+            None => hits.push((event.player, event.damage)),
+        }
+    }
+
+    for (player, damage) in hits {
+        let Ok(health) = players.get_mut(player) else {
+            continue;
+        };
+        let survived = match health {
+            Some(mut health) => health.damage(damage),
+            None => false,
+        };
+        if survived {
+            info!("player hit for {damage}");
+            commands
+                .entity(player)
+                .insert(Invulnerable::new(HIT_INVULNERABLE_SECONDS));
+        } else {
+            player_death_sender.send(PlayerDeathEvent(player));
+        }
+    }
+}
+
 /// Handle player death.
 fn player_death(
     mut event: EventReader<PlayerDeathEvent>,
@@ -203,8 +355,8 @@ fn player_death(
         if let Ok(transform) = query.get(event.0) {
             commands.spawn(DeathAnimation::default().into_bundle(transform, &assets));
         }
-        if let Some(mut entity) = commands.get_entity(event.0) {
-            entity.despawn();
+        if let Some(entity) = commands.get_entity(event.0) {
+            entity.despawn_recursive();
         };
 
         assets.player_death_audio(&mut commands);
@@ -226,11 +378,11 @@ fn enemy_death(
     assets: Res<CollisionAssets>,
 ) {
     for event in event.read() {
-        info!("enemy {:?} died", event.0);
-        if let Ok(transform) = query.get(event.0) {
+        info!("enemy {:?} died", event.entity);
+        if let Ok(transform) = query.get(event.entity) {
             commands.spawn(DeathAnimation::default().into_bundle(transform, &assets));
         }
-        if let Some(mut entity) = commands.get_entity(event.0) {
+        if let Some(mut entity) = commands.get_entity(event.entity) {
             entity.despawn();
         };
         // FIXME: if the player also died, should we suppress this audio?
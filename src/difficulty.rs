@@ -0,0 +1,124 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::level::Level;
+
+/// The difficulty preset chosen before starting a game.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+/// Adjustments made by a difficulty preset.
+///
+/// The scale factors multiply the values from the level data.
+pub struct DifficultySettings {
+    pub lives: usize,
+    pub enemy_speed: f32,
+    pub spawn_rate: f32,
+    pub fire_interval: f32,
+    pub projectile_speed: f32,
+    pub score_multiplier: f32,
+    /// Points needed for each extra life.
+    pub extra_life_every: u32,
+}
+
+const EASY: DifficultySettings = DifficultySettings {
+    lives: 5,
+    enemy_speed: 0.8,
+    spawn_rate: 0.8,
+    fire_interval: 1.5,
+    projectile_speed: 0.75,
+    score_multiplier: 0.5,
+    extra_life_every: 8_000,
+};
+
+const NORMAL: DifficultySettings = DifficultySettings {
+    lives: 3,
+    enemy_speed: 1.0,
+    spawn_rate: 1.0,
+    fire_interval: 1.0,
+    projectile_speed: 1.0,
+    score_multiplier: 1.0,
+    extra_life_every: 10_000,
+};
+
+const HARD: DifficultySettings = DifficultySettings {
+    lives: 3,
+    enemy_speed: 1.2,
+    spawn_rate: 1.25,
+    fire_interval: 0.75,
+    projectile_speed: 1.2,
+    score_multiplier: 1.5,
+    extra_life_every: 15_000,
+};
+
+const INSANE: DifficultySettings = DifficultySettings {
+    lives: 1,
+    enemy_speed: 1.4,
+    spawn_rate: 1.5,
+    fire_interval: 0.5,
+    projectile_speed: 1.4,
+    score_multiplier: 2.5,
+    extra_life_every: 25_000,
+};
+
+impl Difficulty {
+    pub fn settings(self) -> &'static DifficultySettings {
+        match self {
+            Difficulty::Easy => &EASY,
+            Difficulty::Normal => &NORMAL,
+            Difficulty::Hard => &HARD,
+            Difficulty::Insane => &INSANE,
+        }
+    }
+
+    /// The next harder preset, or the same one if it's already the hardest.
+    pub fn harder(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard | Difficulty::Insane => Difficulty::Insane,
+        }
+    }
+
+    /// The next easier preset, or the same one if it's already the easiest.
+    pub fn easier(self) -> Self {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => Difficulty::Easy,
+            Difficulty::Hard => Difficulty::Normal,
+            Difficulty::Insane => Difficulty::Hard,
+        }
+    }
+
+    /// Scale the level settings for this preset.
+    pub fn apply(self, level: &mut Level) {
+        let settings = self.settings();
+        level.enemy_speed *= settings.enemy_speed;
+        level.spawn_rate *= settings.spawn_rate;
+        level.fire_interval *= settings.fire_interval;
+        level.projectile_speed *= settings.projectile_speed;
+    }
+
+    /// Scale a point value for this preset.
+    pub fn score(self, points: u32) -> u32 {
+        (points as f32 * self.settings().score_multiplier).round() as u32
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "EASY",
+            Difficulty::Normal => "NORMAL",
+            Difficulty::Hard => "HARD",
+            Difficulty::Insane => "INSANE",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,154 @@
+//! Option drones that follow the player ship.
+
+use std::f32::consts::TAU;
+
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+use crate::collide::{check_player_collisions, projectile_hitbox, PlayerDeathEvent};
+use crate::difficulty::Difficulty;
+use crate::pickup::{PickupEvent, PickupKind};
+use crate::player::{player_movement, Player};
+use crate::pool::Pooled;
+use crate::ui::Score;
+use crate::weapon::{despawn_projectile, Projectile};
+use crate::GameState;
+
+const MAX_DRONES: usize = 2;
+/// Points for a drone pickup collected with all the drones out.
+const EXTRA_DRONE_POINTS: u32 = 1000;
+const ORBIT_RADIUS: f32 = 36.0;
+/// Radians per second.
+const ORBIT_SPEED: f32 = 3.0;
+/// Where the drones sit while the player is focused.
+const FOCUS_POSITIONS: [Vec2; MAX_DRONES] = [vec2(-28.0, -8.0), vec2(28.0, -8.0)];
+const DRONE_HITBOX: Vec2 = vec2(8.0, 8.0);
+
+pub struct DronePlugin;
+
+impl Plugin for DronePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, DroneAssets::load).add_systems(
+            Update,
+            (
+                add_drones,
+                follow_player.after(player_movement),
+                block_shots.before(check_player_collisions),
+                lose_drones,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct DroneAssets {
+    drone_image: Handle<Image>,
+}
+
+impl DroneAssets {
+    fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let drone_image = asset_server.load("drone.png");
+        commands.insert_resource(DroneAssets { drone_image });
+    }
+}
+
+/// A drone that fires alongside the player and blocks enemy shots.
+///
+/// The weapon fires a copy of each player volley from every drone.
+#[derive(Component)]
+pub struct Drone {
+    /// Position in the formation.
+    index: usize,
+}
+
+/// Add a drone for each drone pickup.
+fn add_drones(
+    mut commands: Commands,
+    mut event: EventReader<PickupEvent>,
+    assets: Res<DroneAssets>,
+    drones: Query<(), With<Drone>>,
+    mut score: ResMut<Score>,
+    difficulty: Res<Difficulty>,
+) {
+    let mut count = drones.iter().count();
+    for event in event.read() {
+        if event.0 != PickupKind::Drone {
+            continue;
+        }
+        if count >= MAX_DRONES {
+            score.0 += difficulty.score(EXTRA_DRONE_POINTS);
+            continue;
+        }
+        info!("drone {count} added");
+        commands.spawn((
+            Sprite::from_image(assets.drone_image.clone_weak()),
+            // Start offscreen; follow_player puts it in place.
+            Transform::from_xyz(0.0, -500.0, 0.0),
+            Drone { index: count },
+        ));
+        count += 1;
+    }
+}
+
+/// Keep the drones with the player ship.
+///
+/// They orbit the ship, and hold still beside it while focused.
+pub fn follow_player(
+    time: Res<Time<Virtual>>,
+    players: Query<(&Transform, &Player), Without<Drone>>,
+    mut drones: Query<(&mut Transform, &Drone)>,
+) {
+    let Ok((player_transform, player)) = players.get_single() else {
+        return;
+    };
+    let center = player_transform.translation.truncate();
+    let count = drones.iter().count();
+    for (mut transform, drone) in &mut drones {
+        let offset = if player.focused {
+            FOCUS_POSITIONS[drone.index % MAX_DRONES]
+        } else {
+            let angle = time.elapsed_secs() * ORBIT_SPEED + TAU * drone.index as f32 / count as f32;
+            Vec2::from_angle(angle) * ORBIT_RADIUS
+        };
+        transform.translation = (center + offset).extend(transform.translation.z);
+    }
+}
+
+/// Drones soak up enemy shots.
+fn block_shots(
+    mut commands: Commands,
+    drones: Query<&Transform, With<Drone>>,
+    projectiles: Query<(&Transform, &Projectile, &Pooled, Entity)>,
+) {
+    for drone_transform in &drones {
+        let drone_box = Aabb2d::new(drone_transform.translation.truncate(), DRONE_HITBOX);
+        for (transform, projectile, pooled, entity) in &projectiles {
+            if !pooled.active() || projectile.player {
+                continue;
+            }
+            let projectile_box = Aabb2d::new(
+                transform.translation.truncate(),
+                projectile_hitbox(projectile),
+            );
+            if drone_box.intersects(&projectile_box) {
+                despawn_projectile(&mut commands, entity);
+            }
+        }
+    }
+}
+
+/// The drones are lost with the ship.
+fn lose_drones(
+    mut commands: Commands,
+    mut event: EventReader<PlayerDeathEvent>,
+    drones: Query<Entity, With<Drone>>,
+) {
+    if event.read().last().is_none() {
+        return;
+    }
+    for entity in &drones {
+        commands.entity(entity).despawn();
+    }
+}
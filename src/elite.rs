@@ -0,0 +1,83 @@
+//! Elite variants of the regular enemies.
+
+use bevy::prelude::*;
+
+/// A modifier that can be applied to any kind of enemy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    /// Takes several hits to destroy.
+    Armored,
+    /// Moves faster than usual.
+    Fast,
+    /// A shield absorbs the first hit.
+    Shielded,
+    /// Splits into two scouts when destroyed.
+    Splitting,
+    /// Throws out a ring of shots when destroyed.
+    Explosive,
+}
+
+impl Modifier {
+    /// The sprite tint for enemies with this modifier.
+    pub fn color(self) -> Color {
+        match self {
+            Modifier::Armored => Color::srgb(1.0, 0.85, 0.3),
+            Modifier::Fast => Color::srgb(1.0, 0.3, 0.3),
+            Modifier::Shielded => Color::srgb(0.4, 0.9, 1.0),
+            Modifier::Splitting => Color::srgb(1.0, 0.4, 1.0),
+            Modifier::Explosive => Color::srgb(1.0, 1.0, 0.2),
+        }
+    }
+
+    /// Hits needed to destroy the enemy.
+    pub fn hits(self) -> u32 {
+        match self {
+            Modifier::Armored => 3,
+            Modifier::Shielded => 2,
+            _ => 1,
+        }
+    }
+
+    /// Speed multiplier.
+    pub fn speed(self) -> f32 {
+        match self {
+            Modifier::Fast => 1.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Extra points for destroying the enemy.
+    pub fn points(self) -> u32 {
+        match self {
+            Modifier::Armored | Modifier::Shielded | Modifier::Explosive => 100,
+            Modifier::Fast | Modifier::Splitting => 50,
+        }
+    }
+}
+
+/// An enemy with a modifier.
+#[derive(Debug, Component)]
+pub struct Elite {
+    pub modifier: Modifier,
+    /// Hits left before the enemy is destroyed.
+    pub hits: u32,
+    /// The tint to use once a shield is gone.
+    pub base_color: Color,
+}
+
+impl Elite {
+    pub fn new(modifier: Modifier, base_color: Color) -> Self {
+        Self {
+            modifier,
+            hits: modifier.hits(),
+            base_color,
+        }
+    }
+
+    /// Take a hit that does `damage` hits' worth of damage.
+    /// Returns true if the enemy survives it.
+    pub fn absorb_hit(&mut self, damage: u32) -> bool {
+        self.hits = self.hits.saturating_sub(damage);
+        self.hits > 0
+    }
+}
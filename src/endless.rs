@@ -0,0 +1,264 @@
+//! Procedurally generated levels, used once the fixed levels run out.
+
+use crate::background::starfield_background;
+use crate::elite::Modifier;
+use crate::enemy::{EscapeRule, ENEMY_PROJECTILE_VELOCITY};
+use crate::formation::{Formation, CIRCLE_FORMATION, GRID_FORMATION, LINE_FORMATION, V_FORMATION};
+use crate::level::Level;
+use crate::path::{EnemyPath, CIRCLE_PATH, LOOP_PATH, SWOOP_PATH, S_DIVE_PATH};
+use crate::pattern::BulletPattern;
+
+/// A value that changes by a fixed amount each endless level, up to a limit.
+#[derive(Debug, Clone, Copy)]
+struct Ramp {
+    base: f32,
+    per_level: f32,
+    limit: f32,
+}
+
+impl Ramp {
+    /// The value at `step` levels into endless mode.
+    fn at(&self, step: usize) -> f32 {
+        let value = self.base + self.per_level * step as f32;
+        if self.per_level >= 0.0 {
+            value.min(self.limit)
+        } else {
+            value.max(self.limit)
+        }
+    }
+}
+
+/// How endless mode gets harder as the level number grows.
+struct DifficultyCurve {
+    /// Points to spend on enemies.
+    budget: Ramp,
+    enemy_speed: Ramp,
+    spawn_rate: Ramp,
+    fire_interval: Ramp,
+    mixed_movement: Ramp,
+    scroll_speed: Ramp,
+    elite_chance: Ramp,
+}
+
+const CURVE: DifficultyCurve = DifficultyCurve {
+    budget: Ramp {
+        base: 22.0,
+        per_level: 4.0,
+        limit: 120.0,
+    },
+    enemy_speed: Ramp {
+        base: 120.0,
+        per_level: 10.0,
+        limit: 250.0,
+    },
+    spawn_rate: Ramp {
+        base: 1.6,
+        per_level: 0.15,
+        limit: 4.0,
+    },
+    fire_interval: Ramp {
+        base: 1.8,
+        per_level: -0.1,
+        limit: 0.6,
+    },
+    mixed_movement: Ramp {
+        base: 0.1,
+        per_level: 0.05,
+        limit: 0.5,
+    },
+    scroll_speed: Ramp {
+        base: 1.3,
+        per_level: 0.1,
+        limit: 2.5,
+    },
+    elite_chance: Ramp {
+        base: 0.15,
+        per_level: 0.03,
+        limit: 0.4,
+    },
+};
+
+/// What an enemy type costs, and when it becomes available.
+struct EnemyCost {
+    /// Budget points spent per enemy.
+    cost: f32,
+    /// The first endless step where this enemy can appear.
+    unlock: usize,
+    /// The level field that counts this enemy type.
+    count: fn(&mut Level) -> &mut usize,
+}
+
+const ENEMY_COSTS: &[EnemyCost] = &[
+    EnemyCost {
+        cost: 1.0,
+        unlock: 0,
+        count: |level| &mut level.num_scout,
+    },
+    EnemyCost {
+        cost: 3.0,
+        unlock: 0,
+        count: |level| &mut level.num_fighter,
+    },
+    EnemyCost {
+        cost: 2.0,
+        unlock: 0,
+        count: |level| &mut level.num_kamikaze,
+    },
+    EnemyCost {
+        cost: 4.0,
+        unlock: 1,
+        count: |level| &mut level.num_bomber,
+    },
+    EnemyCost {
+        cost: 4.0,
+        unlock: 1,
+        count: |level| &mut level.num_turret,
+    },
+    EnemyCost {
+        cost: 10.0,
+        unlock: 3,
+        count: |level| &mut level.num_carrier,
+    },
+    EnemyCost {
+        cost: 5.0,
+        unlock: 0,
+        count: |level| &mut level.num_streams,
+    },
+    EnemyCost {
+        cost: 7.0,
+        unlock: 1,
+        count: |level| &mut level.num_formations,
+    },
+];
+
+const ENDLESS_PATHS: &[EnemyPath] = &[SWOOP_PATH, LOOP_PATH, S_DIVE_PATH, CIRCLE_PATH];
+
+const ENDLESS_FORMATIONS: &[Formation] = &[
+    V_FORMATION,
+    LINE_FORMATION,
+    GRID_FORMATION,
+    CIRCLE_FORMATION,
+];
+
+/// Elite modifiers, in the order they are introduced.
+const ENDLESS_ELITES: &[Modifier] = &[
+    Modifier::Armored,
+    Modifier::Fast,
+    Modifier::Shielded,
+    Modifier::Splitting,
+    Modifier::Explosive,
+];
+
+/// Fighter bullet patterns, and the first step where each can appear.
+const ENDLESS_PATTERNS: &[(BulletPattern, usize)] = &[
+    (BulletPattern::Aimed, 0),
+    (
+        BulletPattern::Fan {
+            count: 3,
+            spread: 0.6,
+        },
+        0,
+    ),
+    (
+        BulletPattern::Burst {
+            count: 3,
+            interval: 0.15,
+        },
+        1,
+    ),
+    (BulletPattern::Ring { count: 8 }, 2),
+    (
+        BulletPattern::Fan {
+            count: 5,
+            spread: 1.2,
+        },
+        3,
+    ),
+    (
+        BulletPattern::Spiral {
+            count: 6,
+            turn: 0.3,
+        },
+        4,
+    ),
+    (BulletPattern::Ring { count: 16 }, 6),
+];
+
+/// Pick the seed for a new game.
+///
+/// Set `SPACESHIP_SEED` (in hex) to replay the endless levels from a shared seed.
+pub fn choose_seed() -> u32 {
+    std::env::var("SPACESHIP_SEED")
+        .ok()
+        .and_then(|seed| u32::from_str_radix(seed.trim(), 16).ok())
+        .unwrap_or_else(|| fastrand::u32(..))
+}
+
+/// Generate a level.
+///
+/// `step` counts levels past the end of the fixed levels, starting at 0.
+/// The same seed and step always produce the same level.
+pub fn endless_level(seed: u32, step: usize) -> Level {
+    let mut rng = fastrand::Rng::with_seed(((seed as u64) << 32) | step as u64);
+
+    let mut level = Level {
+        enemy_speed: CURVE.enemy_speed.at(step),
+        num_scout: 0,
+        num_fighter: 0,
+        num_kamikaze: 0,
+        num_bomber: 0,
+        num_turret: 0,
+        num_carrier: 0,
+        num_streams: 0,
+        paths: ENDLESS_PATHS,
+        num_formations: 0,
+        formations: ENDLESS_FORMATIONS,
+        spawn_rate: CURVE.spawn_rate.at(step),
+        fire_interval: CURVE.fire_interval.at(step),
+        projectile_speed: ENEMY_PROJECTILE_VELOCITY,
+        mixed_movement: CURVE.mixed_movement.at(step),
+        fighter_pattern: BulletPattern::Aimed,
+        background: starfield_background(rng.u64(..)).into(),
+        scroll_speed: CURVE.scroll_speed.at(step),
+        escape: EscapeRule::Escape { penalty: 100 },
+        elite_chance: CURVE.elite_chance.at(step),
+        // The fixed levels introduce three modifiers; add another every other level.
+        elites: &ENDLESS_ELITES[..(3 + step / 2).min(ENDLESS_ELITES.len())],
+    };
+
+    let patterns: Vec<BulletPattern> = ENDLESS_PATTERNS
+        .iter()
+        .filter(|(_, unlock)| *unlock <= step)
+        .map(|(pattern, _)| *pattern)
+        .collect();
+    level.fighter_pattern = patterns[rng.usize(..patterns.len())];
+
+    let available: Vec<&EnemyCost> = ENEMY_COSTS.iter().filter(|e| e.unlock <= step).collect();
+    let mut budget = CURVE.budget.at(step);
+    loop {
+        let affordable: Vec<&&EnemyCost> = available.iter().filter(|e| e.cost <= budget).collect();
+        if affordable.is_empty() {
+            break;
+        }
+        let choice = affordable[rng.usize(..affordable.len())];
+        *(choice.count)(&mut level) += 1;
+        budget -= choice.cost;
+    }
+
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_level() {
+        assert_eq!(endless_level(0x5eed, 4), endless_level(0x5eed, 4));
+    }
+
+    #[test]
+    fn different_seed_gives_different_level() {
+        assert_ne!(endless_level(0x5eed, 4), endless_level(0xbeef, 4));
+    }
+}
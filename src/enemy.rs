@@ -1,163 +1,412 @@
 use std::collections::VecDeque;
+use std::sync::Arc;
 
-use bevy::math::vec2;
+use bevy::math::{vec2, vec3};
 use bevy::prelude::*;
 
-use crate::collide::EnemyDeathEvent;
+use crate::collide::{check_enemy_collisions, Dying, EnemyDeathEvent};
+use crate::difficulty::Difficulty;
+use crate::elite::{Elite, Modifier};
+use crate::formation::{Formation, FormationAnchor};
 use crate::level::{Level, LevelEndEvent, LevelRestartEvent};
-use crate::ui::Score;
-use crate::weapon::{Weapon, WeaponFireEvent};
+use crate::path::{EnemyPath, PathFollower, PathTrack};
+use crate::pattern::{ring, BulletEmitter, BulletPattern};
+use crate::player::Player;
+use crate::ui::{Combo, Score, ShowLevelEvent};
+use crate::weapon::{EnemyVolleyEvent, ProjectileKind};
 use crate::GameState;
 
-const ENEMY_PROJECTILE_VELOCITY: f32 = 400.0;
+pub const ENEMY_PROJECTILE_VELOCITY: f32 = 400.0;
+/// Seconds between scout launches from a carrier.
+const CARRIER_LAUNCH_INTERVAL: f32 = 3.0;
+/// Number of scouts each carrier launches.
+const CARRIER_LAUNCHES: usize = 4;
+/// Kamikazes pick a target once they drift below this height.
+const DIVE_START_Y: f32 = 250.0;
+/// Dive speed, relative to the normal enemy speed.
+const DIVE_SPEED_MULTIPLIER: f32 = 3.0;
+/// Speed along a path, relative to the normal enemy speed.
+const PATH_SPEED_MULTIPLIER: f32 = 2.0;
+/// Number of shots thrown out by an explosive elite.
+const EXPLOSION_SHOTS: usize = 8;
 
 #[derive(Resource)]
 struct EnemyAssets {
     scout_image: Handle<Image>,
     fighter_image: Handle<Image>,
+    kamikaze_image: Handle<Image>,
+    bomber_image: Handle<Image>,
+    turret_image: Handle<Image>,
+    carrier_image: Handle<Image>,
 }
 
 impl EnemyAssets {
     fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
         let scout_image = asset_server.load("green_ship.png");
         let fighter_image = asset_server.load("blue_ship.png");
+        let kamikaze_image = asset_server.load("kamikaze_ship.png");
+        let bomber_image = asset_server.load("bomber_ship.png");
+        let turret_image = asset_server.load("turret_ship.png");
+        let carrier_image = asset_server.load("carrier_ship.png");
 
         commands.insert_resource(EnemyAssets {
             scout_image,
             fighter_image,
+            kamikaze_image,
+            bomber_image,
+            turret_image,
+            carrier_image,
         });
     }
+
+    /// Create a Sprite for an enemy ship.
+    fn enemy_ship(&self, enemy: &Enemy) -> Sprite {
+        let image = match enemy {
+            Enemy::Scout { .. } => &self.scout_image,
+            Enemy::Fighter { .. } => &self.fighter_image,
+            Enemy::Kamikaze { .. } => &self.kamikaze_image,
+            Enemy::Bomber { .. } => &self.bomber_image,
+            Enemy::Turret => &self.turret_image,
+            Enemy::Carrier { .. } => &self.carrier_image,
+        };
+        Sprite {
+            flip_y: true,
+            image: image.clone_weak(),
+            ..default()
+        }
+    }
 }
 
 #[derive(Debug, Default, Resource)]
 pub struct EnemySpawner {
     /// Enemies spawned per second.
     rate: f32,
+    /// Speed of enemies spawned during the level.
+    enemy_speed: f32,
     /// Enemies waiting to be spawned.
-    spawn_queue: VecDeque<Enemy>,
+    spawn_queue: VecDeque<Spawn>,
     /// Number of enemies to kill before the level ends.
     level_remaining: usize,
     /// Time when the next spawn will happen.
     next_spawn: Timer,
+    /// Seconds between shots from each fighter.
+    fire_interval: f32,
+    /// Speed of enemy projectiles.
+    projectile_speed: f32,
+    /// What the fighters shoot.
+    fighter_pattern: BulletPattern,
+    /// Chance that an enemy uses a different movement pattern than usual.
+    mixed_movement: f32,
+    /// Speed of the nearest background layer, for enemies that sit on it.
+    scroll_speed: f32,
+    /// What happens to enemies that leave the screen.
+    escape: EscapeRule,
+    /// Chance that an enemy is an elite.
+    elite_chance: f32,
+    /// Modifiers that elites can have.
+    elites: &'static [Modifier],
+}
+
+/// What happens when an enemy leaves the bottom or sides of the screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum EscapeRule {
+    /// Come back in at the top.
+    #[default]
+    Reenter,
+    /// Leave the level for good, costing this many points.
+    Escape { penalty: u32 },
 }
 
 impl From<&Level> for EnemySpawner {
     fn from(level: &Level) -> Self {
-        let mut spawn_queue = VecDeque::with_capacity(level.num_scout + level.num_fighter);
-        // How many scouts spawn between fighters.
-        let fighter_cadence = level.num_scout / level.num_fighter;
-        for count in 0..level.num_scout {
-            spawn_queue.push_back(Enemy::Scout {
-                speed: level.enemy_speed,
-            });
-
-            if (1 + count) % fighter_cadence == 0 {
-                spawn_queue.push_back(Enemy::Fighter {
-                    speed: level.enemy_speed,
-                })
+        let speed = level.enemy_speed;
+        let groups = [
+            (Enemy::Scout { speed }, level.num_scout),
+            (Enemy::Fighter { speed }, level.num_fighter),
+            (Enemy::Kamikaze { speed }, level.num_kamikaze),
+            (Enemy::Bomber { speed }, level.num_bomber),
+            (Enemy::Turret, level.num_turret),
+            (Enemy::Carrier { speed }, level.num_carrier),
+        ];
+
+        // Spread each type of enemy evenly through the level, by giving each
+        // one a position between 0.0 and 1.0 and sorting.
+        let mut ordered = Vec::new();
+        for (enemy, count) in groups {
+            for n in 0..count {
+                let position = (n as f32 + 0.5) / count as f32;
+                ordered.push((position, Spawn::Single(enemy.clone())));
+            }
+        }
+        if !level.paths.is_empty() {
+            for n in 0..level.num_streams {
+                let position = (n as f32 + 0.5) / level.num_streams as f32;
+                let path = &level.paths[n % level.paths.len()];
+                ordered.push((position, Spawn::Stream(path, speed)));
+            }
+        }
+        if !level.formations.is_empty() {
+            for n in 0..level.num_formations {
+                let position = (n as f32 + 0.5) / level.num_formations as f32;
+                let formation = &level.formations[n % level.formations.len()];
+                ordered.push((position, Spawn::Formation(formation, speed)));
             }
         }
+        ordered.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let spawn_queue: VecDeque<Spawn> = ordered.into_iter().map(|(_, spawn)| spawn).collect();
+        let total = spawn_queue.iter().map(Spawn::len).sum();
+
+        let scroll_speed = level.background.last().map_or(0.0, |layer| layer.speed);
 
         let this = Self {
             rate: level.spawn_rate,
+            enemy_speed: speed,
+            level_remaining: total,
             spawn_queue,
-            level_remaining: level.num_scout + level.num_fighter,
             next_spawn: Timer::from_seconds(3.0, TimerMode::Once),
+            fire_interval: level.fire_interval,
+            projectile_speed: level.projectile_speed,
+            fighter_pattern: level.fighter_pattern,
+            mixed_movement: level.mixed_movement,
+            scroll_speed: scroll_speed * level.scroll_speed,
+            escape: level.escape,
+            elite_chance: level.elite_chance,
+            elites: level.elites,
         };
         info!("{this:?}");
         this
     }
 }
 
-#[derive(Debug, Component)]
+/// An entry in the spawn queue.
+#[derive(Debug)]
+enum Spawn {
+    Single(Enemy),
+    /// A stream of scouts following a path, with this speed.
+    Stream(&'static EnemyPath, f32),
+    /// A formation, with this speed.
+    Formation(&'static Formation, f32),
+}
+
+impl Spawn {
+    /// The number of enemies spawned.
+    fn len(&self) -> usize {
+        match self {
+            Spawn::Single(_) => 1,
+            Spawn::Stream(path, _) => path.stream,
+            Spawn::Formation(formation, _) => formation.count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Component)]
 pub enum Enemy {
-    Scout { speed: f32 },
-    Fighter { speed: f32 },
+    Scout {
+        speed: f32,
+    },
+    Fighter {
+        speed: f32,
+    },
+    /// Dives at the player.
+    Kamikaze {
+        speed: f32,
+    },
+    /// Drops slow bombs.
+    Bomber {
+        speed: f32,
+    },
+    /// Sits on the background and shoots at the player.
+    Turret,
+    /// Launches scouts.
+    Carrier {
+        speed: f32,
+    },
+}
+
+impl Enemy {
+    /// Points for destroying this enemy.
+    pub fn points(&self) -> u32 {
+        match self {
+            Enemy::Scout { .. } => 100,
+            Enemy::Fighter { .. } => 150,
+            Enemy::Kamikaze { .. } => 150,
+            Enemy::Bomber { .. } => 200,
+            Enemy::Turret => 250,
+            Enemy::Carrier { .. } => 500,
+        }
+    }
+
+    /// The same enemy, moving faster or slower.
+    fn with_speed_scale(self, scale: f32) -> Self {
+        match self {
+            Enemy::Scout { speed } => Enemy::Scout {
+                speed: speed * scale,
+            },
+            Enemy::Fighter { speed } => Enemy::Fighter {
+                speed: speed * scale,
+            },
+            Enemy::Kamikaze { speed } => Enemy::Kamikaze {
+                speed: speed * scale,
+            },
+            Enemy::Bomber { speed } => Enemy::Bomber {
+                speed: speed * scale,
+            },
+            Enemy::Turret => Enemy::Turret,
+            Enemy::Carrier { speed } => Enemy::Carrier {
+                speed: speed * scale,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Component)]
 pub enum MovementPattern {
     Zigzag(Vec2),
     LeftRight(f32),
+    /// Drift down, then dive towards where the player was.
+    Dive {
+        speed: f32,
+        /// The dive velocity, once the target is chosen.
+        velocity: Option<Vec2>,
+    },
+    /// Move down at the background scroll speed.
+    Scroll(f32),
+    /// Follow an authored path.
+    Path(PathFollower),
+    /// Hold a position relative to a formation anchor.
+    Formation {
+        anchor: Entity,
+        offset: Vec2,
+        /// Speed used when breaking off to dive.
+        speed: f32,
+    },
 }
 
+/// Launches scouts at regular intervals.
 #[derive(Component)]
-struct WeaponBehavior {
+struct Carrier {
     timer: Timer,
+    launches_left: usize,
 }
 
-impl WeaponBehavior {}
-
 pub struct EnemyBundle {
     enemy: Enemy,
     movement: MovementPattern,
     sprite: Sprite,
     transform: Transform,
-    weapon: Option<Weapon>,
-    weapon_behavior: Option<WeaponBehavior>,
+    emitter: Option<BulletEmitter>,
+    carrier: Option<Carrier>,
+    elite: Option<Elite>,
 }
 
 impl EnemyBundle {
-    fn spawn(self, commands: &mut Commands) {
+    fn spawn(self, commands: &mut Commands) -> Entity {
         let mut commands = commands.spawn((self.enemy, self.movement, self.sprite, self.transform));
-        if let Some(weapon) = self.weapon {
-            commands.insert(weapon);
+        if let Some(emitter) = self.emitter {
+            commands.insert(emitter);
         }
-        if let Some(weapon_behavior) = self.weapon_behavior {
-            commands.insert(weapon_behavior);
+        if let Some(carrier) = self.carrier {
+            commands.insert(carrier);
         }
+        if let Some(elite) = self.elite {
+            commands.insert(elite);
+        }
+        commands.id()
     }
 }
 
 impl Enemy {
-    fn make_bundle(self, assets: &EnemyAssets) -> EnemyBundle {
-        let movement = match &self {
-            Enemy::Scout { speed } => {
-                let x = if fastrand::bool() { *speed } else { -*speed };
-                MovementPattern::Zigzag(vec2(x, *speed))
+    /// Make an enemy, which might turn out to be an elite.
+    fn make_bundle(self, assets: &EnemyAssets, spawner: &EnemySpawner) -> EnemyBundle {
+        let elite = !spawner.elites.is_empty() && fastrand::f32() < spawner.elite_chance;
+        if !elite {
+            return self.make_plain_bundle(assets, spawner);
+        }
+        let modifier = spawner.elites[fastrand::usize(..spawner.elites.len())];
+        info!("elite {modifier:?}");
+        let mut bundle = self
+            .with_speed_scale(modifier.speed())
+            .make_plain_bundle(assets, spawner);
+        bundle.elite = Some(Elite::new(modifier, bundle.sprite.color));
+        bundle.sprite.color = modifier.color();
+        bundle
+    }
+
+    /// Make an enemy with no modifiers.
+    fn make_plain_bundle(self, assets: &EnemyAssets, spawner: &EnemySpawner) -> EnemyBundle {
+        let random_direction = |speed: f32| if fastrand::bool() { speed } else { -speed };
+
+        // Occasionally swap movement patterns, so later levels are less predictable.
+        let mixed = fastrand::f32() < spawner.mixed_movement;
+        let movement = match (&self, mixed) {
+            (Enemy::Scout { speed }, false) | (Enemy::Fighter { speed }, true) => {
+                MovementPattern::Zigzag(vec2(random_direction(*speed), *speed))
             }
-            Enemy::Fighter { speed } => {
-                let x = if fastrand::bool() { *speed } else { -*speed };
-                MovementPattern::LeftRight(x)
+            (Enemy::Fighter { speed }, false) | (Enemy::Scout { speed }, true) => {
+                MovementPattern::LeftRight(random_direction(*speed))
+            }
+            (Enemy::Kamikaze { speed }, _) => MovementPattern::Dive {
+                speed: *speed,
+                velocity: None,
+            },
+            (Enemy::Bomber { speed }, _) => MovementPattern::LeftRight(random_direction(*speed)),
+            (Enemy::Turret, _) => MovementPattern::Scroll(spawner.scroll_speed),
+            (Enemy::Carrier { speed }, _) => {
+                MovementPattern::LeftRight(random_direction(*speed * 0.5))
             }
         };
 
-        let image = match &self {
-            Enemy::Scout { .. } => assets.scout_image.clone_weak(),
-            Enemy::Fighter { .. } => assets.fighter_image.clone_weak(),
-        };
         let rand = fastrand::f32();
         let x = (rand * 400.0) - 200.0;
 
-        let spawn_point = match &self {
-            Enemy::Scout { .. } => vec2(x, 410.0),
-            Enemy::Fighter { .. } => vec2(x, 370.0),
-        };
-
-        let transform = Transform::from_translation(spawn_point.extend(0.0));
-        let sprite = Sprite {
-            flip_y: true,
-            image,
-            ..default()
+        // Enemies that don't move vertically need to start onscreen.
+        let y = match (&self, &movement) {
+            (Enemy::Bomber { .. }, _) => 340.0,
+            (Enemy::Carrier { .. }, _) => 320.0,
+            (_, MovementPattern::LeftRight(_)) => 370.0,
+            _ => 410.0,
         };
 
-        let aim = vec2(0.0, -ENEMY_PROJECTILE_VELOCITY);
-        let (weapon, weapon_behavior) = match &self {
-            Enemy::Scout { .. } => (None, None),
-            Enemy::Fighter { .. } => (
-                Some(Weapon::new(aim, 0.25)),
-                Some(WeaponBehavior {
-                    timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-                }),
+        let transform = Transform::from_translation(vec2(x, y).extend(0.0));
+        let sprite = assets.enemy_ship(&self);
+
+        let emitter = match &self {
+            Enemy::Scout { .. } | Enemy::Kamikaze { .. } | Enemy::Carrier { .. } => None,
+            Enemy::Fighter { .. } => Some(BulletEmitter::new(
+                spawner.fighter_pattern,
+                spawner.projectile_speed,
+                spawner.fire_interval,
+            )),
+            Enemy::Bomber { .. } => Some(
+                BulletEmitter::new(
+                    BulletPattern::Straight,
+                    spawner.projectile_speed * 0.3,
+                    spawner.fire_interval * 1.5,
+                )
+                .with_kind(ProjectileKind::Bomb),
             ),
+            Enemy::Turret => Some(BulletEmitter::new(
+                BulletPattern::Aimed,
+                spawner.projectile_speed,
+                spawner.fire_interval,
+            )),
+        };
+        let carrier = match &self {
+            Enemy::Carrier { .. } => Some(Carrier {
+                timer: Timer::from_seconds(CARRIER_LAUNCH_INTERVAL, TimerMode::Repeating),
+                launches_left: CARRIER_LAUNCHES,
+            }),
+            _ => None,
         };
+
         EnemyBundle {
             enemy: self,
             movement,
             sprite,
             transform,
-            weapon,
-            weapon_behavior,
+            emitter,
+            carrier,
+            elite: None,
         }
     }
 }
@@ -167,17 +416,25 @@ pub struct EnemyPlugin;
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<SpawnerResetEvent>()
+            .add_event::<EnemyEscapeEvent>()
             .insert_resource(EnemySpawner::default())
             .add_systems(Startup, EnemyAssets::load)
             .add_systems(
                 Update,
                 (
                     enemy_spawn,
+                    move_formations,
                     enemy_movement,
+                    // Enemies destroyed this frame can't escape as well.
+                    enemy_escape.after(check_enemy_collisions),
                     enemy_weapons,
+                    aim_turrets,
+                    carrier_launch,
                     level_restart_despawn,
                     reset_spawner,
                     enemy_death,
+                    formation_bonus,
+                    update_shields,
                 )
                     .run_if(in_state(GameState::Playing)),
             );
@@ -186,8 +443,11 @@ impl Plugin for EnemyPlugin {
 
 fn enemy_movement(
     mut enemies: Query<(&mut Transform, &mut MovementPattern), With<Enemy>>,
+    player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    anchors: Query<(&Transform, &FormationAnchor), Without<Enemy>>,
     time: Res<Time<Virtual>>,
 ) {
+    let target = player.get_single().ok();
     for (mut transform, mut movement) in &mut enemies {
         match &mut *movement {
             MovementPattern::LeftRight(x_velocity) => {
@@ -215,28 +475,220 @@ fn enemy_movement(
                     velocity.x = -velocity.x;
                 }
 
-                // vertical bounce
-                if velocity.y > 0.0 {
-                    if transform.translation.y > 400.0 {
-                        velocity.y = -velocity.y;
-                    }
-                } else if transform.translation.y < -400.0 {
+                // Bounce off the top; leaving the bottom is up to `enemy_escape`.
+                if velocity.y > 0.0 && transform.translation.y > 400.0 {
                     velocity.y = -velocity.y;
                 }
             }
+            MovementPattern::Dive { speed, velocity } => {
+                let move_vec = match velocity {
+                    Some(velocity) => *velocity,
+                    None => vec2(0.0, -*speed),
+                };
+                transform.translation += (move_vec * time.delta_secs()).extend(0.0);
+
+                if velocity.is_none() && transform.translation.y < DIVE_START_Y {
+                    // Lock on to where the player is now.
+                    let direction = target
+                        .and_then(|target| {
+                            (target.translation - transform.translation)
+                                .truncate()
+                                .try_normalize()
+                        })
+                        .unwrap_or(Vec2::NEG_Y);
+                    *velocity = Some(direction * *speed * DIVE_SPEED_MULTIPLIER);
+                }
+            }
+            MovementPattern::Path(follower) => {
+                let previous = transform.translation.truncate();
+                if follower.advance(time.delta_secs()) {
+                    let position = follower.position();
+                    transform.translation = position.extend(transform.translation.z);
+                    if let Some(direction) = (position - previous).try_normalize() {
+                        // The sprite is flipped, so it points down.
+                        transform.rotation = Quat::from_rotation_arc_2d(Vec2::NEG_Y, direction);
+                    }
+                } else {
+                    // The path is finished; bounce around the screen instead.
+                    let speed = follower.speed / 2.0;
+                    let x = -speed.copysign(transform.translation.x);
+                    let y = -speed.copysign(transform.translation.y);
+                    transform.rotation = Quat::IDENTITY;
+                    *movement = MovementPattern::Zigzag(vec2(x, y));
+                }
+            }
+            MovementPattern::Formation {
+                anchor,
+                offset,
+                speed,
+            } => {
+                let Ok((anchor_transform, anchor)) = anchors.get(*anchor) else {
+                    continue;
+                };
+                let position = anchor_transform.translation.truncate() + *offset;
+                transform.translation = position.extend(transform.translation.z);
+
+                // Once the formation is in place, members occasionally dive at the player.
+                let breakoff = anchor.dive_chance * time.delta_secs();
+                if anchor.in_position(anchor_transform) && fastrand::f32() < breakoff {
+                    let direction = target
+                        .and_then(|target| {
+                            (target.translation - transform.translation)
+                                .truncate()
+                                .try_normalize()
+                        })
+                        .unwrap_or(Vec2::NEG_Y);
+                    info!("formation member breaking off");
+                    *movement = MovementPattern::Dive {
+                        speed: *speed,
+                        velocity: Some(direction * *speed * DIVE_SPEED_MULTIPLIER),
+                    };
+                }
+            }
+            MovementPattern::Scroll(speed) => {
+                transform.translation.y -= *speed * time.delta_secs();
+            }
+        }
+    }
+}
+
+/// An enemy left the screen for good.
+#[derive(Event)]
+pub struct EnemyEscapeEvent(pub Entity);
+
+/// Deal with enemies that have left the bottom or sides of the screen.
+#[expect(clippy::type_complexity)]
+fn enemy_escape(
+    mut commands: Commands,
+    mut enemies: Query<
+        (&mut Transform, &mut MovementPattern, Entity),
+        (With<Enemy>, Without<Dying>),
+    >,
+    mut spawner: ResMut<EnemySpawner>,
+    mut score: ResMut<Score>,
+    mut escape_sender: EventWriter<EnemyEscapeEvent>,
+    mut level_end: EventWriter<LevelEndEvent>,
+) {
+    for (mut transform, mut movement, entity) in &mut enemies {
+        // Paths are allowed to wander offscreen.
+        if matches!(*movement, MovementPattern::Path(_)) {
+            continue;
+        }
+        let loc = transform.translation;
+        if loc.y > -420.0 && loc.x.abs() < 220.0 {
+            continue;
+        }
+
+        match spawner.escape {
+            EscapeRule::Reenter => {
+                transform.translation.x = (fastrand::f32() * 400.0) - 200.0;
+                transform.translation.y = 410.0;
+                if let MovementPattern::Dive { velocity, .. } = &mut *movement {
+                    *velocity = None;
+                }
+            }
+            EscapeRule::Escape { penalty } => {
+                info!("enemy {entity:?} escaped");
+                commands.entity(entity).try_insert(Dying).despawn();
+                escape_sender.send(EnemyEscapeEvent(entity));
+                score.0 = score.0.saturating_sub(penalty);
+                spawner.level_remaining = spawner.level_remaining.checked_sub(1).unwrap();
+                if spawner.level_remaining == 0 {
+                    level_end.send(LevelEndEvent);
+                }
+            }
         }
     }
 }
 
+/// Fly formation anchors in from the top, then sway side to side.
+fn move_formations(
+    mut anchors: Query<(&mut Transform, &mut FormationAnchor)>,
+    time: Res<Time<Virtual>>,
+) {
+    for (mut transform, mut anchor) in &mut anchors {
+        if !anchor.in_position(&transform) {
+            transform.translation.y -= anchor.speed.abs() * time.delta_secs();
+            continue;
+        }
+        transform.translation.x += anchor.speed * time.delta_secs();
+        let limit = (200.0 - anchor.half_width).max(0.0);
+        if (anchor.speed > 0.0 && transform.translation.x > limit)
+            || (anchor.speed < 0.0 && transform.translation.x < -limit)
+        {
+            anchor.speed = -anchor.speed;
+        }
+    }
+}
+
+/// Turn turrets to face the player.
+fn aim_turrets(
+    mut turrets: Query<(&mut Transform, &Enemy), Without<Player>>,
+    player: Query<&Transform, With<Player>>,
+) {
+    let Ok(target) = player.get_single() else {
+        return;
+    };
+    for (mut transform, enemy) in &mut turrets {
+        if !matches!(enemy, Enemy::Turret) {
+            continue;
+        }
+        let aim = (target.translation - transform.translation).truncate();
+        if let Some(direction) = aim.try_normalize() {
+            // The sprite is flipped, so it points down.
+            transform.rotation = Quat::from_rotation_arc_2d(Vec2::NEG_Y, direction);
+        }
+    }
+}
+
+/// Launch scouts from carriers.
+///
+/// Each launched scout adds to the number of enemies left in the level.
+fn carrier_launch(
+    mut commands: Commands,
+    assets: Res<EnemyAssets>,
+    time: Res<Time<Virtual>>,
+    mut spawner: ResMut<EnemySpawner>,
+    mut carriers: Query<(&mut Carrier, &Transform, &Enemy)>,
+) {
+    for (mut carrier, transform, enemy) in &mut carriers {
+        if carrier.launches_left == 0 {
+            continue;
+        }
+        carrier.timer.tick(time.delta());
+        if !carrier.timer.just_finished() {
+            continue;
+        }
+        carrier.launches_left -= 1;
+
+        let Enemy::Carrier { speed } = enemy else {
+            continue;
+        };
+        let scout = Enemy::Scout { speed: *speed };
+        info!("carrier launch {scout:?}");
+        let mut bundle = scout.make_bundle(&assets, &spawner);
+        bundle.transform.translation = transform.translation;
+        bundle.spawn(&mut commands);
+        spawner.level_remaining += 1;
+    }
+}
+
 fn enemy_weapons(
     time: Res<Time<Virtual>>,
-    mut enemies: Query<(&mut WeaponBehavior, Entity), With<Enemy>>,
-    mut event_sender: EventWriter<WeaponFireEvent>,
+    mut enemies: Query<(&mut BulletEmitter, &Transform), With<Enemy>>,
+    player: Query<&Transform, With<Player>>,
+    mut event_sender: EventWriter<EnemyVolleyEvent>,
 ) {
-    for (mut behavior, entity) in &mut enemies {
-        behavior.timer.tick(time.delta());
-        if behavior.timer.just_finished() {
-            event_sender.send(WeaponFireEvent(entity));
+    let target = player.get_single().ok();
+    for (mut emitter, transform) in &mut enemies {
+        let aim = target.map(|target| (target.translation - transform.translation).truncate());
+        let velocities = emitter.tick(time.delta(), aim);
+        if !velocities.is_empty() {
+            event_sender.send(EnemyVolleyEvent {
+                origin: transform.translation,
+                kind: emitter.kind(),
+                velocities,
+            });
         }
     }
 }
@@ -247,11 +699,11 @@ fn enemy_spawn(
     time: Res<Time<Virtual>>,
     mut spawner: ResMut<EnemySpawner>,
 ) {
-    let enemy;
+    let spawn;
     // If the timer has expired, reset it if there are still enemies left to spawn.
     if spawner.next_spawn.just_finished() {
         match spawner.spawn_queue.pop_front() {
-            Some(e) => enemy = e,
+            Some(s) => spawn = s,
             None => {
                 // no more spawning to do.
                 return;
@@ -265,9 +717,68 @@ fn enemy_spawn(
         return;
     }
 
-    // Spawn a new enemy.
-    info!("spawn enemy {enemy:?}");
-    enemy.make_bundle(&assets).spawn(&mut commands);
+    match spawn {
+        Spawn::Single(enemy) => {
+            // Spawn a new enemy.
+            info!("spawn enemy {enemy:?}");
+            enemy.make_bundle(&assets, &spawner).spawn(&mut commands);
+        }
+        Spawn::Stream(path, speed) => {
+            // Spawn a stream of scouts that all follow the same path.
+            info!("spawn stream of {}", path.stream);
+            let track = Arc::new(PathTrack::new(path));
+            let mirror = fastrand::bool();
+            for n in 0..path.stream {
+                let path_speed = speed * PATH_SPEED_MULTIPLIER;
+                let time_offset = n as f32 * path.spacing;
+                let mut follower = PathFollower::new(track.clone(), path, path_speed, time_offset);
+                follower.mirror = mirror;
+
+                let mut bundle = Enemy::Scout { speed }.make_bundle(&assets, &spawner);
+                bundle.transform.translation = follower.position().extend(0.0);
+                bundle.movement = MovementPattern::Path(follower);
+                bundle.spawn(&mut commands);
+            }
+        }
+        Spawn::Formation(formation, speed) => {
+            info!("spawn formation {:?}", formation.shape);
+            let offsets = formation.offsets();
+            let half_width = offsets.iter().map(|o| o.x.abs()).fold(0.0, f32::max);
+            let top = offsets.iter().map(|o| o.y).fold(0.0, f32::max);
+            // Start with the whole formation above the screen.
+            let start = vec2((fastrand::f32() - 0.5) * 2.0 * (200.0 - half_width), 420.0);
+            let anchor = commands
+                .spawn(Transform::from_translation(start.extend(0.0)))
+                .id();
+
+            let mut members = Vec::new();
+            for offset in offsets {
+                let enemy = if formation.fighters {
+                    Enemy::Fighter { speed }
+                } else {
+                    Enemy::Scout { speed }
+                };
+                let mut bundle = enemy.make_bundle(&assets, &spawner);
+                bundle.transform.translation = (start + offset).extend(0.0);
+                bundle.movement = MovementPattern::Formation {
+                    anchor,
+                    offset,
+                    speed,
+                };
+                members.push(bundle.spawn(&mut commands));
+            }
+            let speed = if fastrand::bool() { speed } else { -speed };
+            commands.entity(anchor).insert(FormationAnchor {
+                members,
+                intact: true,
+                bonus: formation.bonus,
+                dive_chance: formation.dive_chance,
+                speed: speed * 0.5,
+                half_width,
+                hold_y: 330.0 - top,
+            });
+        }
+    }
 }
 
 /// Load level settings and reset the spawner.
@@ -287,6 +798,7 @@ fn level_restart_despawn(
     mut event: EventReader<LevelRestartEvent>,
     mut spawner: ResMut<EnemySpawner>,
     enemies: Query<Entity, With<Enemy>>,
+    anchors: Query<Entity, With<FormationAnchor>>,
 ) {
     let Some(_) = event.read().last() else {
         return;
@@ -294,23 +806,104 @@ fn level_restart_despawn(
     // Disable the timer so no new enemies will spawn.
     spawner.next_spawn.pause();
 
-    for entity in &enemies {
+    for entity in enemies.iter().chain(&anchors) {
         commands.entity(entity).despawn();
     }
 }
 
 /// Count dead enemies and bump the level on the last one.
+///
+/// Elites with death effects split or explode here.
+#[expect(clippy::too_many_arguments)]
 fn enemy_death(
+    mut commands: Commands,
+    assets: Res<EnemyAssets>,
     mut event: EventReader<EnemyDeathEvent>,
     mut spawner: ResMut<EnemySpawner>,
     mut level_end: EventWriter<LevelEndEvent>,
+    mut volley: EventWriter<EnemyVolleyEvent>,
     mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    difficulty: Res<Difficulty>,
 ) {
-    for _event in event.read() {
-        score.0 += 100;
+    for event in event.read() {
+        match event.modifier {
+            Some(Modifier::Splitting) => {
+                for direction in [-1.0, 1.0] {
+                    let speed = spawner.enemy_speed;
+                    let mut bundle = Enemy::Scout { speed }.make_plain_bundle(&assets, &spawner);
+                    bundle.transform.translation =
+                        event.position + vec3(direction * 15.0, 0.0, 0.0);
+                    bundle.movement = MovementPattern::Zigzag(vec2(direction * speed, -speed));
+                    bundle.spawn(&mut commands);
+                    spawner.level_remaining += 1;
+                }
+            }
+            Some(Modifier::Explosive) => {
+                let speed = spawner.projectile_speed * 0.5;
+                volley.send(EnemyVolleyEvent {
+                    origin: event.position,
+                    kind: ProjectileKind::Bomb,
+                    velocities: ring(Vec2::NEG_Y, EXPLOSION_SHOTS)
+                        .map(|direction| direction * speed)
+                        .collect(),
+                });
+            }
+            _ => {}
+        }
+
+        score.0 += combo.kill(difficulty.score(event.points));
         spawner.level_remaining = spawner.level_remaining.checked_sub(1).unwrap();
         if spawner.level_remaining == 0 {
             level_end.send(LevelEndEvent);
         }
     }
 }
+
+/// Award a bonus when every member of a formation is destroyed.
+fn formation_bonus(
+    mut commands: Commands,
+    mut event: EventReader<EnemyDeathEvent>,
+    mut escapes: EventReader<EnemyEscapeEvent>,
+    mut anchors: Query<(&mut FormationAnchor, Entity)>,
+    mut score: ResMut<Score>,
+    mut bonus_text: EventWriter<ShowLevelEvent>,
+    difficulty: Res<Difficulty>,
+) {
+    let escaped: Vec<Entity> = escapes.read().map(|event| event.0).collect();
+    for &entity in &escaped {
+        for (mut anchor, _) in &mut anchors {
+            if anchor.members.contains(&entity) {
+                anchor.intact = false;
+            }
+        }
+    }
+
+    let removed = event.read().map(|event| event.entity).chain(escaped);
+    for entity in removed {
+        for (mut anchor, anchor_entity) in &mut anchors {
+            let Some(index) = anchor.members.iter().position(|&e| e == entity) else {
+                continue;
+            };
+            anchor.members.swap_remove(index);
+            if anchor.members.is_empty() {
+                if anchor.intact {
+                    let bonus = difficulty.score(anchor.bonus);
+                    info!("formation bonus {bonus}");
+                    score.0 += bonus;
+                    bonus_text.send(ShowLevelEvent(format!("FORMATION BONUS\n\n{bonus}")));
+                }
+                commands.entity(anchor_entity).despawn();
+            }
+        }
+    }
+}
+
+/// Drop an elite's shield tint once the shield is gone.
+fn update_shields(mut elites: Query<(&Elite, &mut Sprite), Changed<Elite>>) {
+    for (elite, mut sprite) in &mut elites {
+        if elite.modifier == Modifier::Shielded && elite.hits < Modifier::Shielded.hits() {
+            sprite.color = elite.base_color;
+        }
+    }
+}
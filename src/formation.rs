@@ -0,0 +1,136 @@
+//! Groups of enemies that fly together.
+
+use std::f32::consts::TAU;
+
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+/// How the members of a formation are arranged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormationShape {
+    /// A V, with the leader at the front.
+    V,
+    /// A horizontal line.
+    Line,
+    /// Rows, filled from the front.
+    Grid { columns: usize },
+    /// A ring around the anchor.
+    Circle,
+}
+
+/// A group of enemies that spawn together and move around a shared anchor.
+#[derive(Debug, PartialEq)]
+pub struct Formation {
+    pub shape: FormationShape,
+    /// Number of enemies in the formation.
+    pub count: usize,
+    /// Distance between neighboring members.
+    pub spacing: f32,
+    /// Members are fighters rather than scouts.
+    pub fighters: bool,
+    /// Chance per second that each member breaks off to dive at the player.
+    pub dive_chance: f32,
+    /// Points for destroying every member.
+    pub bonus: u32,
+}
+
+impl Formation {
+    /// Each member's position relative to the anchor.
+    pub fn offsets(&self) -> Vec<Vec2> {
+        let spacing = self.spacing;
+        let count = self.count;
+        match self.shape {
+            FormationShape::V => (0..count)
+                .map(|n| {
+                    // Alternate sides, each pair further back than the last.
+                    let rank = n.div_ceil(2) as f32;
+                    let side = if n % 2 == 0 { 1.0 } else { -1.0 };
+                    vec2(side * rank * spacing, rank * spacing * 0.8)
+                })
+                .collect(),
+            FormationShape::Line => {
+                let width = (count.max(1) - 1) as f32 * spacing;
+                (0..count)
+                    .map(|n| vec2(n as f32 * spacing - width / 2.0, 0.0))
+                    .collect()
+            }
+            FormationShape::Grid { columns } => {
+                let columns = columns.clamp(1, count.max(1));
+                let width = (columns - 1) as f32 * spacing;
+                (0..count)
+                    .map(|n| {
+                        let (row, column) = (n / columns, n % columns);
+                        vec2(column as f32 * spacing - width / 2.0, row as f32 * spacing)
+                    })
+                    .collect()
+            }
+            FormationShape::Circle => {
+                let radius = spacing * count as f32 / TAU;
+                (0..count)
+                    .map(|n| Vec2::from_angle(TAU * n as f32 / count as f32) * radius)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The point a formation moves around.
+#[derive(Component)]
+pub struct FormationAnchor {
+    /// Members that haven't been destroyed yet.
+    pub members: Vec<Entity>,
+    /// False once any member has left without being destroyed.
+    pub intact: bool,
+    pub bonus: u32,
+    pub dive_chance: f32,
+    /// Sideways speed, once in position. The sign is the direction; the
+    /// fly-in uses the same speed, downwards.
+    pub speed: f32,
+    /// How far the members extend to either side of the anchor.
+    pub half_width: f32,
+    /// The anchor descends until it reaches this height.
+    pub hold_y: f32,
+}
+
+impl FormationAnchor {
+    /// True once the formation has finished flying in.
+    pub fn in_position(&self, transform: &Transform) -> bool {
+        transform.translation.y <= self.hold_y
+    }
+}
+
+pub const V_FORMATION: Formation = Formation {
+    shape: FormationShape::V,
+    count: 5,
+    spacing: 35.0,
+    fighters: false,
+    dive_chance: 0.1,
+    bonus: 500,
+};
+
+pub const LINE_FORMATION: Formation = Formation {
+    shape: FormationShape::Line,
+    count: 6,
+    spacing: 40.0,
+    fighters: false,
+    dive_chance: 0.08,
+    bonus: 600,
+};
+
+pub const GRID_FORMATION: Formation = Formation {
+    shape: FormationShape::Grid { columns: 3 },
+    count: 6,
+    spacing: 45.0,
+    fighters: true,
+    dive_chance: 0.05,
+    bonus: 1000,
+};
+
+pub const CIRCLE_FORMATION: Formation = Formation {
+    shape: FormationShape::Circle,
+    count: 8,
+    spacing: 30.0,
+    fighters: false,
+    dive_chance: 0.06,
+    bonus: 800,
+};
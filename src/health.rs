@@ -0,0 +1,210 @@
+//! The optional shield and hull model for the player.
+
+use std::fmt;
+
+use bevy::math::vec2;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::player::Player;
+
+const MAX_SHIELD: f32 = 100.0;
+const MAX_HULL: f32 = 100.0;
+/// Seconds after a hit before the shield starts to recharge.
+const SHIELD_REGEN_DELAY: f32 = 2.0;
+/// Shield points recharged per second.
+const SHIELD_REGEN_RATE: f32 = 25.0;
+/// Width of the shield and hull bars when full.
+const BAR_WIDTH: f32 = 120.0;
+
+/// How the player takes damage.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum HealthModel {
+    /// Any hit destroys the ship.
+    #[default]
+    Arcade,
+    /// A regenerating shield protects the hull; the ship is destroyed
+    /// when the hull runs out.
+    Shields,
+}
+
+impl HealthModel {
+    pub fn toggle(self) -> Self {
+        match self {
+            HealthModel::Arcade => HealthModel::Shields,
+            HealthModel::Shields => HealthModel::Arcade,
+        }
+    }
+}
+
+impl fmt::Display for HealthModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HealthModel::Arcade => "ARCADE",
+            HealthModel::Shields => "SHIELDS",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The player's shield and hull, when using `HealthModel::Shields`.
+#[derive(Component)]
+pub struct Health {
+    pub shield: f32,
+    pub hull: f32,
+    /// Time since the last hit, until the shield recharges.
+    regen_delay: Timer,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            shield: MAX_SHIELD,
+            hull: MAX_HULL,
+            regen_delay: Timer::from_seconds(SHIELD_REGEN_DELAY, TimerMode::Once),
+        }
+    }
+}
+
+impl Health {
+    /// Take some damage, shield first. Returns false once the hull is gone.
+    pub fn damage(&mut self, damage: f32) -> bool {
+        let absorbed = damage.min(self.shield);
+        self.shield -= absorbed;
+        self.hull = (self.hull - (damage - absorbed)).max(0.0);
+        self.regen_delay.reset();
+        self.hull > 0.0
+    }
+}
+
+/// The bubble around the ship while the shield is up.
+#[derive(Component)]
+struct ShieldSprite;
+
+/// A HUD bar showing the shield or hull.
+#[derive(Component)]
+enum HealthBar {
+    Shield,
+    Hull,
+}
+
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HealthModel::default())
+            .add_systems(Startup, (HealthAssets::load, create_bars))
+            .add_systems(
+                Update,
+                (
+                    add_health,
+                    regenerate_shields,
+                    update_shield_sprites,
+                    update_bars,
+                ),
+            );
+    }
+}
+
+#[derive(Resource)]
+struct HealthAssets {
+    shield_image: Handle<Image>,
+}
+
+impl HealthAssets {
+    fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
+        let shield_image = asset_server.load("shield.png");
+        commands.insert_resource(HealthAssets { shield_image });
+    }
+}
+
+/// Give new player ships a shield and hull, if the rules call for it.
+fn add_health(
+    mut commands: Commands,
+    model: Res<HealthModel>,
+    assets: Res<HealthAssets>,
+    players: Query<Entity, Added<Player>>,
+) {
+    if *model != HealthModel::Shields {
+        return;
+    }
+    for entity in &players {
+        let shield = commands
+            .spawn((
+                Sprite {
+                    image: assets.shield_image.clone_weak(),
+                    color: Color::srgba(0.4, 0.8, 1.0, 0.5),
+                    ..default()
+                },
+                // In front of the ship, behind the hitbox display.
+                Transform::from_xyz(0.0, 0.0, 0.05),
+                ShieldSprite,
+            ))
+            .id();
+        commands
+            .entity(entity)
+            .insert(Health::default())
+            .add_child(shield);
+    }
+}
+
+fn regenerate_shields(time: Res<Time<Virtual>>, mut players: Query<&mut Health>) {
+    for mut health in &mut players {
+        health.regen_delay.tick(time.delta());
+        if health.regen_delay.finished() {
+            health.shield = (health.shield + SHIELD_REGEN_RATE * time.delta_secs()).min(MAX_SHIELD);
+        }
+    }
+}
+
+/// Fade the shield glow as the shield weakens.
+fn update_shield_sprites(
+    players: Query<(&Health, &Children)>,
+    mut shields: Query<&mut Sprite, With<ShieldSprite>>,
+) {
+    for (health, children) in &players {
+        for &child in children {
+            if let Ok(mut sprite) = shields.get_mut(child) {
+                sprite.color.set_alpha(0.5 * health.shield / MAX_SHIELD);
+            }
+        }
+    }
+}
+
+fn create_bars(mut commands: Commands) {
+    let bar = |color: Color, y: f32| {
+        (
+            Sprite {
+                color,
+                custom_size: Some(vec2(BAR_WIDTH, 5.0)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            Transform::from_xyz(-BAR_WIDTH / 2.0, y, -1.0),
+            Visibility::Hidden,
+        )
+    };
+    commands.spawn((bar(Color::srgb(0.4, 0.8, 1.0), -384.0), HealthBar::Shield));
+    commands.spawn((bar(Color::srgb(1.0, 0.3, 0.2), -392.0), HealthBar::Hull));
+}
+
+/// Show the shield and hull of the current player ship.
+fn update_bars(
+    players: Query<&Health, With<Player>>,
+    mut bars: Query<(&HealthBar, &mut Sprite, &mut Visibility)>,
+) {
+    let health = players.get_single().ok();
+    for (bar, mut sprite, mut visibility) in &mut bars {
+        let fraction = health.map(|health| match bar {
+            HealthBar::Shield => health.shield / MAX_SHIELD,
+            HealthBar::Hull => health.hull / MAX_HULL,
+        });
+        match fraction {
+            Some(fraction) => {
+                sprite.custom_size = Some(vec2(BAR_WIDTH * fraction, 5.0));
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
@@ -1,38 +1,182 @@
-use std::cmp::min;
+use std::borrow::Cow;
+use std::time::Duration;
 
 use bevy::prelude::*;
 
-use crate::enemy::SpawnerResetEvent;
+use crate::background::{BackgroundResetEvent, BgLayer, BgSource, Starfield, DEFAULT_BACKGROUND};
+use crate::difficulty::Difficulty;
+use crate::elite::Modifier;
+use crate::endless::{choose_seed, endless_level};
+use crate::enemy::{EscapeRule, SpawnerResetEvent, ENEMY_PROJECTILE_VELOCITY};
+use crate::formation::{Formation, GRID_FORMATION, V_FORMATION};
+use crate::path::{EnemyPath, LOOP_PATH, SWOOP_PATH, S_DIVE_PATH};
+use crate::pattern::BulletPattern;
 use crate::player::PlayerSpawnEvent;
+use crate::results::{LevelStats, ShowResultsEvent, RESULTS_SECONDS};
 use crate::ui::ShowLevelEvent;
 use crate::GameState;
 
-#[derive(Debug, Clone)]
+/// Seconds between the start of a game, or a restart, and the level starting.
+const LEVEL_START_SECONDS: f32 = 2.0;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Level {
     pub enemy_speed: f32,
     pub num_scout: usize,
     pub num_fighter: usize,
+    pub num_kamikaze: usize,
+    pub num_bomber: usize,
+    pub num_turret: usize,
+    pub num_carrier: usize,
+    /// Number of scout streams that fly along `paths`.
+    pub num_streams: usize,
+    /// Paths for the scout streams, used in turn.
+    pub paths: &'static [EnemyPath],
+    /// Number of formations, chosen from `formations` in turn.
+    pub num_formations: usize,
+    pub formations: &'static [Formation],
     pub spawn_rate: f32,
+    /// Seconds between shots from each fighter.
+    pub fire_interval: f32,
+    /// Speed of enemy projectiles, in pixels per second.
+    pub projectile_speed: f32,
+    /// Chance that an enemy uses a different movement pattern than usual.
+    pub mixed_movement: f32,
+    /// What the fighters shoot.
+    pub fighter_pattern: BulletPattern,
+    pub background: Cow<'static, [BgLayer]>,
+    /// Multiplier for the background scroll speed.
+    pub scroll_speed: f32,
+    /// What happens to enemies that leave the screen.
+    pub escape: EscapeRule,
+    /// Chance that an enemy is an elite.
+    pub elite_chance: f32,
+    /// Modifiers that elites can have.
+    pub elites: &'static [Modifier],
 }
 
+/// Distant stars with a layer of near stars on top.
+const NEAR_STARS_BACKGROUND: &[BgLayer] = &[
+    BgLayer {
+        source: BgSource::Image("stars1.png"),
+        speed: 25.0,
+        color: Color::srgb(0.7, 0.7, 0.8),
+        flip_x: false,
+    },
+    BgLayer {
+        source: BgSource::Starfield(Starfield {
+            seed: 1,
+            stars: 40,
+            min_size: 2.0,
+            max_size: 3.0,
+            twinkle: 0.2,
+        }),
+        speed: 70.0,
+        color: Color::WHITE,
+        flip_x: false,
+    },
+];
+
+/// Star layers behind a reddish nebula haze.
+const NEBULA_BACKGROUND: &[BgLayer] = &[
+    BgLayer {
+        source: BgSource::Image("stars1.png"),
+        speed: 20.0,
+        color: Color::srgb(0.6, 0.6, 0.7),
+        flip_x: false,
+    },
+    BgLayer {
+        source: BgSource::Image("stars1.png"),
+        speed: 45.0,
+        color: Color::srgba(0.9, 0.4, 0.8, 0.3),
+        flip_x: true,
+    },
+    BgLayer {
+        source: BgSource::Starfield(Starfield {
+            seed: 2,
+            stars: 50,
+            min_size: 2.0,
+            max_size: 3.0,
+            twinkle: 0.4,
+        }),
+        speed: 90.0,
+        color: Color::srgb(1.0, 0.9, 0.8),
+        flip_x: false,
+    },
+];
+
 const LEVELS: &[Level] = &[
     Level {
         enemy_speed: 90.0,
         num_scout: 5,
         num_fighter: 1,
+        num_kamikaze: 0,
+        num_bomber: 0,
+        num_turret: 0,
+        num_carrier: 0,
+        num_streams: 0,
+        paths: &[],
+        num_formations: 0,
+        formations: &[],
         spawn_rate: 0.5,
+        fire_interval: 2.0,
+        projectile_speed: ENEMY_PROJECTILE_VELOCITY,
+        mixed_movement: 0.0,
+        fighter_pattern: BulletPattern::Straight,
+        background: Cow::Borrowed(DEFAULT_BACKGROUND),
+        scroll_speed: 1.0,
+        escape: EscapeRule::Reenter,
+        elite_chance: 0.0,
+        elites: &[],
     },
     Level {
         enemy_speed: 100.0,
         num_scout: 8,
         num_fighter: 2,
+        num_kamikaze: 2,
+        num_bomber: 0,
+        num_turret: 0,
+        num_carrier: 0,
+        num_streams: 1,
+        paths: &[SWOOP_PATH],
+        num_formations: 1,
+        formations: &[V_FORMATION],
         spawn_rate: 1.0,
+        fire_interval: 2.0,
+        projectile_speed: ENEMY_PROJECTILE_VELOCITY,
+        mixed_movement: 0.0,
+        fighter_pattern: BulletPattern::Aimed,
+        background: Cow::Borrowed(NEAR_STARS_BACKGROUND),
+        scroll_speed: 1.1,
+        escape: EscapeRule::Escape { penalty: 50 },
+        elite_chance: 0.1,
+        elites: &[Modifier::Armored],
     },
     Level {
         enemy_speed: 110.0,
         num_scout: 12,
         num_fighter: 3,
+        num_kamikaze: 3,
+        num_bomber: 1,
+        num_turret: 1,
+        num_carrier: 0,
+        num_streams: 2,
+        paths: &[LOOP_PATH, S_DIVE_PATH],
+        num_formations: 1,
+        formations: &[GRID_FORMATION],
         spawn_rate: 1.5,
+        fire_interval: 2.0,
+        projectile_speed: ENEMY_PROJECTILE_VELOCITY,
+        mixed_movement: 0.0,
+        fighter_pattern: BulletPattern::Fan {
+            count: 3,
+            spread: 0.5,
+        },
+        background: Cow::Borrowed(NEBULA_BACKGROUND),
+        scroll_speed: 1.2,
+        escape: EscapeRule::Escape { penalty: 100 },
+        elite_chance: 0.15,
+        elites: &[Modifier::Armored, Modifier::Fast, Modifier::Shielded],
     },
 ];
 
@@ -41,6 +185,9 @@ pub struct CurrentLevel {
     pub number: usize,
     pub level: Level,
     pub level_start_timer: Timer,
+    /// Seed for the endless levels that follow the fixed ones.
+    pub seed: u32,
+    pub stats: LevelStats,
 }
 
 impl Default for CurrentLevel {
@@ -48,7 +195,9 @@ impl Default for CurrentLevel {
         Self {
             number: 0,
             level: LEVELS[0].clone(),
-            level_start_timer: Timer::from_seconds(2.0, TimerMode::Once),
+            level_start_timer: Timer::from_seconds(LEVEL_START_SECONDS, TimerMode::Once),
+            seed: choose_seed(),
+            stats: LevelStats::default(),
         }
     }
 }
@@ -81,16 +230,27 @@ fn level_start(
     mut spawn_player: EventWriter<PlayerSpawnEvent>,
     mut enemies: EventWriter<SpawnerResetEvent>,
     mut level_text: EventWriter<ShowLevelEvent>,
+    mut background: EventWriter<BackgroundResetEvent>,
+    difficulty: Res<Difficulty>,
 ) {
     current_level.level_start_timer.tick(time.delta());
 
     if current_level.level_start_timer.just_finished() {
-        info!("start level {}", current_level.number);
+        info!(
+            "start level {} (seed {:08X})",
+            current_level.number, current_level.seed
+        );
 
         level_text.send(ShowLevelEvent(format!("LEVEL {}", current_level.number)));
 
         spawn_player.send(PlayerSpawnEvent);
-        enemies.send(SpawnerResetEvent(current_level.level.clone()));
+        let mut level = current_level.level.clone();
+        difficulty.apply(&mut level);
+        enemies.send(SpawnerResetEvent(level));
+        background.send(BackgroundResetEvent {
+            layers: current_level.level.background.clone(),
+            speed_scale: current_level.level.scroll_speed,
+        });
     }
 }
 
@@ -100,23 +260,36 @@ fn detect_player_death(
     mut event: EventReader<LevelRestartEvent>,
 ) {
     if event.read().last().is_some() {
-        current_level.level_start_timer.reset();
+        // The timer may still have the longer delay for the results screen.
+        let timer = &mut current_level.level_start_timer;
+        timer.set_duration(Duration::from_secs_f32(LEVEL_START_SECONDS));
+        timer.reset();
     };
 }
 
-/// If the player completed the level, load the next one.
-fn bump_level(mut current_level: ResMut<CurrentLevel>, mut event: EventReader<LevelEndEvent>) {
+/// If the player completed the level, show the results and load the next one.
+fn bump_level(
+    mut current_level: ResMut<CurrentLevel>,
+    mut event: EventReader<LevelEndEvent>,
+    mut results: EventWriter<ShowResultsEvent>,
+) {
     if event.read().last().is_some() {
+        results.send(ShowResultsEvent {
+            level: current_level.number,
+            stats: current_level.stats.clone(),
+        });
+
         let level_number = current_level.number + 1;
-        let level_index = min(level_number, LEVELS.len() - 1);
-        let mut level = LEVELS[level_index].clone();
-        if level_number >= 3 {
-            level.enemy_speed += (level_number - 2) as f32 * 15.0;
-        }
+        let level = match LEVELS.get(level_number) {
+            Some(level) => level.clone(),
+            None => endless_level(current_level.seed, level_number - LEVELS.len()),
+        };
         *current_level = CurrentLevel {
             number: level_number,
             level,
-            level_start_timer: Timer::from_seconds(2.0, TimerMode::Once),
+            level_start_timer: Timer::from_seconds(RESULTS_SECONDS, TimerMode::Once),
+            seed: current_level.seed,
+            stats: LevelStats::default(),
         };
     };
 }
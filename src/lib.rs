@@ -0,0 +1,31 @@
+//! The game, as a library, so the benchmarks can use it.
+
+use bevy::prelude::*;
+
+pub mod background;
+pub mod bomb;
+pub mod collide;
+pub mod difficulty;
+pub mod drone;
+pub mod elite;
+pub mod endless;
+pub mod enemy;
+pub mod formation;
+pub mod health;
+pub mod level;
+pub mod path;
+pub mod pattern;
+pub mod pickup;
+pub mod player;
+pub mod pool;
+pub mod results;
+pub mod ui;
+pub mod weapon;
+
+#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy, States)]
+pub enum GameState {
+    #[default]
+    Idle,
+    Playing,
+    Paused,
+}
@@ -3,21 +3,19 @@ use bevy::prelude::*;
 use bevy::render::camera::{ScalingMode, Viewport};
 use bevy::window::{PresentMode, WindowResized, WindowResolution};
 
-use crate::background::BgPlugin;
-use crate::collide::CollisionPlugin;
-use crate::enemy::EnemyPlugin;
-use crate::level::LevelPlugin;
-use crate::player::PlayerPlugin;
-use crate::ui::UiPlugin;
-use crate::weapon::WeaponsPlugin;
-
-mod background;
-mod collide;
-mod enemy;
-mod level;
-mod player;
-mod ui;
-mod weapon;
+use spaceship::background::BgPlugin;
+use spaceship::bomb::BombPlugin;
+use spaceship::collide::CollisionPlugin;
+use spaceship::drone::DronePlugin;
+use spaceship::enemy::EnemyPlugin;
+use spaceship::health::HealthPlugin;
+use spaceship::level::LevelPlugin;
+use spaceship::pickup::PickupPlugin;
+use spaceship::player::PlayerPlugin;
+use spaceship::results::ResultsPlugin;
+use spaceship::ui::UiPlugin;
+use spaceship::weapon::WeaponsPlugin;
+use spaceship::GameState;
 
 const PRESENT_MODE: PresentMode = if cfg!(target_family = "wasm") {
     PresentMode::Fifo
@@ -25,14 +23,6 @@ const PRESENT_MODE: PresentMode = if cfg!(target_family = "wasm") {
     PresentMode::Mailbox
 };
 
-#[derive(Debug, Default, Hash, PartialEq, Eq, Clone, Copy, States)]
-enum GameState {
-    #[default]
-    Idle,
-    Playing,
-    Paused,
-}
-
 fn main() {
     App::new()
         .insert_resource(ClearColor(Color::srgb_u8(1, 1, 1)))
@@ -70,9 +60,14 @@ fn main() {
             BgPlugin,
             PlayerPlugin,
             WeaponsPlugin,
+            BombPlugin,
             EnemyPlugin,
             CollisionPlugin,
+            HealthPlugin,
+            PickupPlugin,
+            DronePlugin,
             LevelPlugin,
+            ResultsPlugin,
             UiPlugin,
         ))
         .run();
@@ -0,0 +1,223 @@
+//! Authored flight paths for enemies.
+
+use std::sync::Arc;
+
+use bevy::math::cubic_splines::{CubicBezier, CubicCardinalSpline, CubicCurve, CubicGenerator};
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+/// Number of straight lines used to approximate each curve segment.
+const SUBDIVISIONS: usize = 16;
+
+/// The control points of a path, in screen coordinates.
+#[derive(Debug, PartialEq)]
+pub enum PathShape {
+    /// Cubic Bezier segments, each with its own four control points.
+    Bezier(&'static [[Vec2; 4]]),
+    /// A Catmull-Rom spline. The curve passes through every point
+    /// except the first and last, which only set the direction at the ends.
+    CatmullRom(&'static [Vec2]),
+}
+
+/// A path that a stream of enemies flies along.
+#[derive(Debug, PartialEq)]
+pub struct EnemyPath {
+    pub shape: PathShape,
+    /// Start over from the beginning after reaching the end.
+    pub looping: bool,
+    /// Number of enemies that fly this path, one after another.
+    pub stream: usize,
+    /// Seconds between the enemies in a stream.
+    pub spacing: f32,
+}
+
+impl EnemyPath {
+    fn curve(&self) -> CubicCurve<Vec2> {
+        match self.shape {
+            PathShape::Bezier(points) => CubicBezier::new(points).to_curve().unwrap(),
+            PathShape::CatmullRom(points) => CubicCardinalSpline::new_catmull_rom(points)
+                .to_curve()
+                .unwrap(),
+        }
+    }
+}
+
+/// A path flattened into straight lines, so enemies can move along it
+/// at a constant speed.
+#[derive(Debug)]
+pub struct PathTrack {
+    points: Vec<Vec2>,
+    /// Distance along the path to each point.
+    distances: Vec<f32>,
+}
+
+impl PathTrack {
+    pub fn new(path: &EnemyPath) -> Self {
+        let curve = path.curve();
+        let points: Vec<Vec2> = curve
+            .iter_positions(curve.segments().len() * SUBDIVISIONS)
+            .collect();
+
+        let mut distances = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (n, point) in points.iter().enumerate() {
+            if n > 0 {
+                total += point.distance(points[n - 1]);
+            }
+            distances.push(total);
+        }
+        Self { points, distances }
+    }
+
+    /// The total length of the path.
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// The position at some distance along the path.
+    ///
+    /// Distances outside the path are clamped to the start or end.
+    pub fn position(&self, distance: f32) -> Vec2 {
+        let next = self.distances.partition_point(|&d| d < distance);
+        if next == 0 {
+            return self.points[0];
+        }
+        if next == self.points.len() {
+            return self.points[next - 1];
+        }
+        let (d0, d1) = (self.distances[next - 1], self.distances[next]);
+        let fraction = (distance - d0) / (d1 - d0);
+        self.points[next - 1].lerp(self.points[next], fraction)
+    }
+}
+
+/// Moves an enemy along a path.
+#[derive(Debug)]
+pub struct PathFollower {
+    pub track: Arc<PathTrack>,
+    /// Distance travelled along the path. Negative values wait at the start.
+    pub distance: f32,
+    /// Speed along the path, in pixels per second.
+    pub speed: f32,
+    pub looping: bool,
+    /// Fly a mirror image of the path.
+    pub mirror: bool,
+}
+
+impl PathFollower {
+    /// `time_offset` delays this enemy, so a stream can share one path.
+    pub fn new(track: Arc<PathTrack>, path: &EnemyPath, speed: f32, time_offset: f32) -> Self {
+        Self {
+            track,
+            distance: -time_offset * speed,
+            speed,
+            looping: path.looping,
+            mirror: false,
+        }
+    }
+
+    /// The current position.
+    pub fn position(&self) -> Vec2 {
+        let position = self.track.position(self.distance);
+        if self.mirror {
+            vec2(-position.x, position.y)
+        } else {
+            position
+        }
+    }
+
+    /// Move along the path. Returns false once a non-looping path is finished.
+    pub fn advance(&mut self, delta_secs: f32) -> bool {
+        self.distance += self.speed * delta_secs;
+        let length = self.track.length();
+        if self.distance < length {
+            return true;
+        }
+        if self.looping && length > 0.0 {
+            self.distance %= length;
+            return true;
+        }
+        false
+    }
+}
+
+/// Swoop in from the top left, dip towards the middle, and leave on the right.
+const SWOOP: &[Vec2] = &[
+    vec2(-300.0, 500.0),
+    vec2(-180.0, 420.0),
+    vec2(-140.0, 200.0),
+    vec2(-40.0, 20.0),
+    vec2(80.0, 60.0),
+    vec2(150.0, 220.0),
+    vec2(240.0, 260.0),
+    vec2(320.0, 260.0),
+];
+
+/// Come down the middle, loop around once, and go back up.
+const LOOP: &[Vec2] = &[
+    vec2(0.0, 520.0),
+    vec2(0.0, 420.0),
+    vec2(0.0, 100.0),
+    vec2(-90.0, -20.0),
+    vec2(0.0, -120.0),
+    vec2(90.0, -20.0),
+    vec2(0.0, 100.0),
+    vec2(-120.0, 260.0),
+    vec2(-160.0, 420.0),
+    vec2(-160.0, 520.0),
+];
+
+/// S-curve dive from the top to the bottom of the screen.
+const S_DIVE: &[[Vec2; 4]] = &[
+    [
+        vec2(100.0, 420.0),
+        vec2(-250.0, 300.0),
+        vec2(250.0, 100.0),
+        vec2(0.0, 0.0),
+    ],
+    [
+        vec2(0.0, 0.0),
+        vec2(-250.0, -100.0),
+        vec2(250.0, -300.0),
+        vec2(-100.0, -420.0),
+    ],
+];
+
+/// Circle the upper half of the screen, passing just above the top each lap.
+const CIRCLE: &[Vec2] = &[
+    vec2(0.0, 520.0),
+    vec2(0.0, 440.0),
+    vec2(130.0, 250.0),
+    vec2(0.0, 120.0),
+    vec2(-130.0, 250.0),
+    vec2(0.0, 440.0),
+    vec2(130.0, 250.0),
+];
+
+pub const SWOOP_PATH: EnemyPath = EnemyPath {
+    shape: PathShape::CatmullRom(SWOOP),
+    looping: false,
+    stream: 4,
+    spacing: 0.4,
+};
+
+pub const LOOP_PATH: EnemyPath = EnemyPath {
+    shape: PathShape::CatmullRom(LOOP),
+    looping: false,
+    stream: 5,
+    spacing: 0.3,
+};
+
+pub const S_DIVE_PATH: EnemyPath = EnemyPath {
+    shape: PathShape::Bezier(S_DIVE),
+    looping: false,
+    stream: 3,
+    spacing: 0.5,
+};
+
+pub const CIRCLE_PATH: EnemyPath = EnemyPath {
+    shape: PathShape::CatmullRom(CIRCLE),
+    looping: true,
+    stream: 4,
+    spacing: 0.6,
+};
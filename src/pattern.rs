@@ -0,0 +1,145 @@
+//! Bullet patterns for enemy weapons.
+
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::weapon::ProjectileKind;
+
+/// The shape of a volley of enemy shots.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BulletPattern {
+    /// One shot straight down.
+    #[default]
+    Straight,
+    /// One shot at the player.
+    Aimed,
+    /// Shots spread evenly across an arc centered on the player.
+    Fan {
+        count: usize,
+        /// Total width of the arc, in radians.
+        spread: f32,
+    },
+    /// Shots in every direction.
+    Ring { count: usize },
+    /// A ring that rotates a little after every volley.
+    Spiral {
+        count: usize,
+        /// Rotation per volley, in radians.
+        turn: f32,
+    },
+    /// Several shots at the player in quick succession.
+    Burst {
+        count: usize,
+        /// Seconds between the shots in a burst.
+        interval: f32,
+    },
+}
+
+/// Fires a bullet pattern on a timer.
+#[derive(Component)]
+pub struct BulletEmitter {
+    pattern: BulletPattern,
+    kind: ProjectileKind,
+    /// Projectile speed, in pixels per second.
+    speed: f32,
+    /// Time between volleys.
+    timer: Timer,
+    /// The current rotation of a spiral.
+    angle: f32,
+    /// Shots left in the current burst.
+    burst_remaining: usize,
+    /// Time until the next shot in the current burst.
+    burst_timer: Timer,
+}
+
+impl BulletEmitter {
+    pub fn new(pattern: BulletPattern, speed: f32, interval: f32) -> Self {
+        let burst_interval = match pattern {
+            BulletPattern::Burst { interval, .. } => interval,
+            _ => 0.0,
+        };
+        Self {
+            pattern,
+            kind: ProjectileKind::default(),
+            speed,
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+            angle: 0.0,
+            burst_remaining: 0,
+            burst_timer: Timer::from_seconds(burst_interval, TimerMode::Repeating),
+        }
+    }
+
+    /// Fire a different kind of projectile.
+    pub fn with_kind(self, kind: ProjectileKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    pub fn kind(&self) -> ProjectileKind {
+        self.kind
+    }
+
+    /// Advance the timers, and return the velocities of any shots fired.
+    ///
+    /// `aim` is the direction towards the player, if there is one.
+    pub fn tick(&mut self, delta: Duration, aim: Option<Vec2>) -> Vec<Vec2> {
+        // Without a target, aimed shots go straight down.
+        let aim = aim.and_then(Vec2::try_normalize).unwrap_or(Vec2::NEG_Y);
+        let mut directions = Vec::new();
+
+        if self.burst_remaining > 0 {
+            self.burst_timer.tick(delta);
+            if self.burst_timer.just_finished() {
+                self.burst_remaining -= 1;
+                directions.push(aim);
+            }
+        }
+
+        self.timer.tick(delta);
+        if self.timer.just_finished() {
+            match self.pattern {
+                BulletPattern::Straight => directions.push(Vec2::NEG_Y),
+                BulletPattern::Aimed => directions.push(aim),
+                BulletPattern::Fan { count, spread } => {
+                    directions.extend(fan(aim, count, spread));
+                }
+                BulletPattern::Ring { count } => {
+                    directions.extend(ring(Vec2::NEG_Y, count));
+                }
+                BulletPattern::Spiral { count, turn } => {
+                    let start = Vec2::from_angle(self.angle).rotate(Vec2::NEG_Y);
+                    directions.extend(ring(start, count));
+                    self.angle = (self.angle + turn) % TAU;
+                }
+                BulletPattern::Burst { count, .. } => {
+                    directions.push(aim);
+                    self.burst_remaining = count.saturating_sub(1);
+                    self.burst_timer.reset();
+                }
+            }
+        }
+
+        directions
+            .into_iter()
+            .map(|direction| direction * self.speed)
+            .collect()
+    }
+}
+
+/// Directions spread evenly across an arc.
+fn fan(center: Vec2, count: usize, spread: f32) -> impl Iterator<Item = Vec2> {
+    let step = if count > 1 {
+        spread / (count - 1) as f32
+    } else {
+        0.0
+    };
+    let first = -spread / 2.0;
+    (0..count.max(1)).map(move |n| Vec2::from_angle(first + step * n as f32).rotate(center))
+}
+
+/// Directions spread evenly around a circle.
+pub fn ring(start: Vec2, count: usize) -> impl Iterator<Item = Vec2> {
+    let step = TAU / count.max(1) as f32;
+    (0..count.max(1)).map(move |n| Vec2::from_angle(step * n as f32).rotate(start))
+}
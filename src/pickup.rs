@@ -0,0 +1,154 @@
+//! Pickups dropped by destroyed enemies.
+
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use bevy::math::vec2;
+use bevy::prelude::*;
+
+use crate::collide::{EnemyDeathEvent, PLAYER_HITBOX};
+use crate::difficulty::Difficulty;
+use crate::level::LevelRestartEvent;
+use crate::player::Player;
+use crate::ui::Score;
+use crate::weapon::WeaponTier;
+use crate::GameState;
+
+/// Chance that a regular enemy drops a power-up. Elites always drop one.
+const POWER_UP_CHANCE: f32 = 0.04;
+/// Chance that a regular enemy drops a drone.
+const DRONE_CHANCE: f32 = 0.02;
+/// Points for a power-up collected with the weapon already at the top tier.
+const MAX_TIER_POINTS: u32 = 1000;
+const PICKUP_SIZE: Vec2 = vec2(10.0, 10.0);
+const PICKUP_HITBOX: Vec2 = vec2(8.0, 8.0);
+/// Speed the pickups drift down the screen.
+const PICKUP_SPEED: f32 = 60.0;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickupEvent>().add_systems(
+            Update,
+            (
+                drop_pickups,
+                move_pickups,
+                collect_pickups,
+                power_up,
+                level_restart_despawn,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupKind {
+    /// Moves the player weapon up a tier.
+    PowerUp,
+    /// Adds a drone.
+    Drone,
+}
+
+impl PickupKind {
+    fn color(self) -> Color {
+        match self {
+            PickupKind::PowerUp => Color::srgb(1.0, 0.6, 0.2),
+            PickupKind::Drone => Color::srgb(0.4, 1.0, 0.6),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Pickup(pub PickupKind);
+
+/// The player collected a pickup.
+#[derive(Event)]
+pub struct PickupEvent(pub PickupKind);
+
+/// Spawn a pickup at a position.
+fn spawn_pickup(commands: &mut Commands, kind: PickupKind, position: Vec3) {
+    commands.spawn((
+        Sprite::from_color(kind.color(), PICKUP_SIZE),
+        Transform::from_translation(position),
+        Pickup(kind),
+    ));
+}
+
+/// Sometimes leave a pickup behind when an enemy is destroyed.
+fn drop_pickups(mut commands: Commands, mut event: EventReader<EnemyDeathEvent>) {
+    for event in event.read() {
+        let roll = fastrand::f32();
+        if event.modifier.is_some() || roll < POWER_UP_CHANCE {
+            spawn_pickup(&mut commands, PickupKind::PowerUp, event.position);
+        } else if roll < POWER_UP_CHANCE + DRONE_CHANCE {
+            spawn_pickup(&mut commands, PickupKind::Drone, event.position);
+        }
+    }
+}
+
+/// Drift the pickups down the screen, spinning.
+fn move_pickups(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut query: Query<(&mut Transform, Entity), With<Pickup>>,
+) {
+    for (mut transform, entity) in &mut query {
+        transform.translation.y -= PICKUP_SPEED * time.delta_secs();
+        transform.rotate_z(3.0 * time.delta_secs());
+        if transform.translation.y < -420.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    players: Query<&Transform, With<Player>>,
+    pickups: Query<(&Transform, &Pickup, Entity)>,
+    mut pickup_sender: EventWriter<PickupEvent>,
+) {
+    let Ok(player_transform) = players.get_single() else {
+        return;
+    };
+    let player_box = Aabb2d::new(player_transform.translation.truncate(), PLAYER_HITBOX);
+    for (transform, pickup, entity) in &pickups {
+        let pickup_box = Aabb2d::new(transform.translation.truncate(), PICKUP_HITBOX);
+        if !pickup_box.intersects(&player_box) {
+            continue;
+        }
+        commands.entity(entity).despawn();
+        pickup_sender.send(PickupEvent(pickup.0));
+    }
+}
+
+fn power_up(
+    mut event: EventReader<PickupEvent>,
+    mut weapon_tier: ResMut<WeaponTier>,
+    mut score: ResMut<Score>,
+    difficulty: Res<Difficulty>,
+) {
+    for event in event.read() {
+        if event.0 != PickupKind::PowerUp {
+            continue;
+        }
+        if weapon_tier.upgrade() {
+            info!("weapon upgraded to tier {}", weapon_tier.0);
+        } else {
+            score.0 += difficulty.score(MAX_TIER_POINTS);
+        }
+    }
+}
+
+/// Clear away pickups when the level restarts.
+fn level_restart_despawn(
+    mut commands: Commands,
+    mut event: EventReader<LevelRestartEvent>,
+    pickups: Query<Entity, With<Pickup>>,
+) {
+    let Some(_) = event.read().last() else {
+        return;
+    };
+    for entity in &pickups {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,12 +1,26 @@
 use bevy::math::vec2;
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 
-use crate::weapon::{Weapon, WeaponFireEvent};
+use crate::bomb::SmartBombEvent;
+use crate::collide::PLAYER_HITBOX;
+use crate::weapon::{SecondaryWeapon, Weapon, WeaponChargeEvent, WeaponFireEvent, WeaponSlot};
 use crate::GameState;
 
 const PLAYER_SPEED: f32 = 200.0;
 const PLAYER_PROJECTILE_VELOCITY: f32 = 400.0;
 const PLAYER_SPAWN_POSITION: Vec2 = vec2(0.0, -300.0);
+/// Speed multiplier while focused.
+const FOCUS_SPEED: f32 = 0.4;
+const DASH_SPEED: f32 = 1000.0;
+const DASH_SECONDS: f32 = 0.15;
+const DASH_COOLDOWN: f32 = 1.5;
+/// Seconds between afterimages left by a dash.
+const AFTERIMAGE_INTERVAL: f32 = 0.03;
+/// Seconds for an afterimage to fade out.
+const AFTERIMAGE_SECONDS: f32 = 0.25;
+/// Width of the charge meter when fully charged.
+const CHARGE_METER_WIDTH: f32 = 24.0;
 
 #[derive(Resource)]
 struct PlayerAssets {
@@ -29,22 +43,82 @@ impl PlayerAssets {
 #[derive(Component)]
 pub struct Player {
     pub speed: f32,
+    /// Slow, precise movement while the focus button is held.
+    ///
+    /// Weapons with a spread narrow it while focused.
+    pub focused: bool,
 }
 
 impl Default for Player {
     fn default() -> Self {
         Self {
             speed: PLAYER_SPEED,
+            focused: false,
         }
     }
 }
 
+/// A quick burst of movement, with invulnerability.
+#[derive(Component)]
+pub struct Dash {
+    /// Time until the next dash is allowed.
+    cooldown: Timer,
+    /// Time left in the current dash.
+    active: Timer,
+    velocity: Vec2,
+    /// Time until the next afterimage.
+    afterimage: Timer,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        let mut cooldown = Timer::from_seconds(DASH_COOLDOWN, TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        let mut active = Timer::from_seconds(DASH_SECONDS, TimerMode::Once);
+        active.tick(active.duration());
+        Self {
+            cooldown,
+            active,
+            velocity: Vec2::ZERO,
+            afterimage: Timer::from_seconds(AFTERIMAGE_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl Dash {
+    pub fn dashing(&self) -> bool {
+        !self.active.finished()
+    }
+
+    /// How far the cooldown has recharged, from 0.0 to 1.0.
+    pub fn charge(&self) -> f32 {
+        self.cooldown.fraction()
+    }
+}
+
+/// Shows the player's hitbox while focused.
+#[derive(Component)]
+struct HitboxMarker;
+
+/// Shows how far the weapon has charged.
+///
+/// The weapon only charges while fire is held in focus; an unfocused hold
+/// autofires.
+#[derive(Component)]
+struct ChargeMeter;
+
+/// A fading copy of the ship, left behind by a dash.
+#[derive(Component)]
+struct Afterimage(Timer);
+
 #[derive(Bundle)]
 pub struct PlayerBundle {
     player: Player,
     sprite: Sprite,
     transform: Transform,
     weapon: Weapon,
+    secondary: SecondaryWeapon,
+    dash: Dash,
 }
 
 impl Default for PlayerBundle {
@@ -59,6 +133,8 @@ impl Default for PlayerBundle {
             sprite: Default::default(),
             transform: Default::default(),
             weapon,
+            secondary: SecondaryWeapon::default(),
+            dash: Dash::default(),
         }
     }
 }
@@ -71,8 +147,129 @@ impl Plugin for PlayerPlugin {
             .add_systems(Startup, PlayerAssets::load)
             .add_systems(
                 Update,
-                (spawn_player, player_movement).run_if(in_state(GameState::Playing)),
-            );
+                (
+                    spawn_player,
+                    player_movement,
+                    invulnerability,
+                    dash_trail,
+                    show_hitbox,
+                    update_charge_meter,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, fade_afterimages);
+    }
+}
+
+/// The player can't be hurt until the timer runs out.
+#[derive(Component)]
+pub struct Invulnerable(Timer);
+
+impl Invulnerable {
+    pub fn new(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, TimerMode::Once))
+    }
+}
+
+/// Blink the player ship while invulnerable.
+fn invulnerability(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut players: Query<(&mut Invulnerable, &mut Sprite, Entity), With<Player>>,
+) {
+    for (mut invulnerable, mut sprite, entity) in &mut players {
+        invulnerable.0.tick(time.delta());
+        if invulnerable.0.finished() {
+            sprite.color.set_alpha(1.0);
+            commands.entity(entity).remove::<Invulnerable>();
+        } else {
+            let blink = (invulnerable.0.elapsed_secs() * 5.0).fract() < 0.5;
+            sprite.color.set_alpha(if blink { 0.3 } else { 1.0 });
+        }
+    }
+}
+
+/// Show the hitbox of a focused ship.
+fn show_hitbox(
+    players: Query<(&Player, &Children), Changed<Player>>,
+    mut markers: Query<&mut Visibility, With<HitboxMarker>>,
+) {
+    for (player, children) in &players {
+        for &child in children {
+            if let Ok(mut visibility) = markers.get_mut(child) {
+                *visibility = if player.focused {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                };
+            }
+        }
+    }
+}
+
+/// Grow the charge meter as the weapon charges.
+fn update_charge_meter(
+    players: Query<(&Weapon, &Children), With<Player>>,
+    mut meters: Query<(&mut Sprite, &mut Visibility), With<ChargeMeter>>,
+) {
+    for (weapon, children) in &players {
+        let charge = weapon.charge_fraction();
+        for &child in children {
+            if let Ok((mut sprite, mut visibility)) = meters.get_mut(child) {
+                if charge == 0.0 {
+                    *visibility = Visibility::Hidden;
+                    continue;
+                }
+                *visibility = Visibility::Inherited;
+                sprite.custom_size = Some(vec2(CHARGE_METER_WIDTH * charge, 3.0));
+                // Turn from cyan to white at full charge.
+                sprite.color = if charge < 1.0 {
+                    Color::srgb(0.3, 0.9, 1.0)
+                } else {
+                    Color::WHITE
+                };
+            }
+        }
+    }
+}
+
+/// Leave afterimages behind a dashing ship.
+fn dash_trail(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut players: Query<(&Transform, &mut Dash), With<Player>>,
+    assets: Res<PlayerAssets>,
+) {
+    for (transform, mut dash) in &mut players {
+        if !dash.dashing() {
+            continue;
+        }
+        dash.afterimage.tick(time.delta());
+        if dash.afterimage.just_finished() {
+            let mut sprite = assets.player_ship();
+            sprite.color = Color::srgba(1.0, 0.6, 0.6, 0.5);
+            commands.spawn((
+                sprite,
+                transform.with_translation(transform.translation - Vec3::Z),
+                Afterimage(Timer::from_seconds(AFTERIMAGE_SECONDS, TimerMode::Once)),
+            ));
+        }
+    }
+}
+
+fn fade_afterimages(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut query: Query<(&mut Afterimage, &mut Sprite, Entity)>,
+) {
+    for (mut afterimage, mut sprite, entity) in &mut query {
+        afterimage.0.tick(time.delta());
+        sprite
+            .color
+            .set_alpha(0.5 * afterimage.0.fraction_remaining());
+        if afterimage.0.finished() {
+            commands.entity(entity).despawn();
+        }
     }
 }
 
@@ -88,12 +285,22 @@ fn tune_analog(input: f32) -> f32 {
     value.clamp(0.0, 1.0) * sign
 }
 
+#[expect(clippy::too_many_arguments)]
 pub fn player_movement(
-    mut players: Query<(&mut Transform, &Player, Entity)>,
+    mut commands: Commands,
+    mut players: Query<(
+        &mut Transform,
+        &mut Player,
+        &mut Dash,
+        Has<Invulnerable>,
+        Entity,
+    )>,
     keyboard: Res<ButtonInput<KeyCode>>,
     gamepads: Query<&Gamepad>,
     time: Res<Time<Virtual>>,
     mut event_sender: EventWriter<WeaponFireEvent>,
+    mut charge_sender: EventWriter<WeaponChargeEvent>,
+    mut bomb_sender: EventWriter<SmartBombEvent>,
 ) {
     // for ScanCode(code) in keyboard.get_pressed() {
     //     info!("scancode {code:#x}");
@@ -101,47 +308,110 @@ pub fn player_movement(
 
     // We could iterate, but since we're hard-coding the
     // keyboard controls, that wouldn't make sense.
-    let Ok((mut transform, player, entity)) = players.get_single_mut() else {
+    let Ok((mut transform, mut player, mut dash, invulnerable, entity)) = players.get_single_mut()
+    else {
         return;
     };
 
-    let move_delta = player.speed * time.delta_secs();
-
     let mut analog_x = None;
     let mut analog_y = None;
     let mut fire_button = false;
+    let mut fire_held = false;
+    let mut secondary_button = false;
+    let mut bomb_button = false;
+    let mut dash_button = false;
+    let mut focus_button = false;
     // FIXME: gracefully handle >1 gamepads
     if let Some(gamepad) = gamepads.iter().next() {
         let analog = gamepad.left_stick().map(tune_analog);
         analog_x = Some(analog.x);
         analog_y = Some(analog.y);
         fire_button = gamepad.digital().just_pressed(GamepadButton::South);
+        fire_held = gamepad.digital().pressed(GamepadButton::South);
+        secondary_button = gamepad.digital().pressed(GamepadButton::North);
+        bomb_button = gamepad.digital().just_pressed(GamepadButton::East);
+        dash_button = gamepad.digital().just_pressed(GamepadButton::West);
+        focus_button = gamepad.digital().pressed(GamepadButton::LeftTrigger);
     }
 
+    // The direction of movement, with each axis between -1.0 and 1.0.
+    let mut direction = Vec2::ZERO;
     if keyboard.pressed(KeyCode::ArrowUp) {
-        transform.translation.y += move_delta;
+        direction.y = 1.0;
     } else if keyboard.pressed(KeyCode::ArrowDown) {
-        transform.translation.y -= move_delta;
+        direction.y = -1.0;
     } else if let Some(y_value) = analog_y {
-        transform.translation.y += move_delta * y_value;
+        direction.y = y_value;
     }
 
     if keyboard.pressed(KeyCode::ArrowLeft) {
-        transform.translation.x -= move_delta;
+        direction.x = -1.0;
     } else if keyboard.pressed(KeyCode::ArrowRight) {
-        transform.translation.x += move_delta;
+        direction.x = 1.0;
     } else if let Some(x_value) = analog_x {
-        transform.translation.x += move_delta * x_value;
+        direction.x = x_value;
     }
 
+    dash.cooldown.tick(time.delta());
+    dash.active.tick(time.delta());
+    if (keyboard.just_pressed(KeyCode::KeyX) || dash_button) && dash.cooldown.finished() {
+        // Dash the way we're moving, or straight ahead if we're not.
+        let dash_direction = direction.try_normalize().unwrap_or(Vec2::Y);
+        dash.velocity = dash_direction * DASH_SPEED;
+        dash.cooldown.reset();
+        dash.active.reset();
+        dash.afterimage.reset();
+        if !invulnerable {
+            commands
+                .entity(entity)
+                .insert(Invulnerable::new(DASH_SECONDS + 0.1));
+        }
+    }
+
+    let focused = keyboard.pressed(KeyCode::ShiftLeft) || focus_button;
+    // Avoid triggering change detection every frame.
+    if player.focused != focused {
+        player.focused = focused;
+    }
+    let speed = if player.focused {
+        player.speed * FOCUS_SPEED
+    } else {
+        player.speed
+    };
+
+    let velocity = if dash.dashing() {
+        dash.velocity
+    } else {
+        direction * speed
+    };
+    transform.translation += (velocity * time.delta_secs()).extend(0.0);
+
     const PLAYER_BOUNDS: Vec2 = Vec2 { x: 180.0, y: 380.0 };
 
     // Don't let the ship travel offscreen.
     let extents = PLAYER_BOUNDS.extend(0.0);
     transform.translation = transform.translation.min(extents).max(-extents);
 
-    if keyboard.just_pressed(KeyCode::Space) || fire_button {
-        event_sender.send(WeaponFireEvent(entity));
+    // Holding the fire button autofires as fast as the weapon recharges;
+    // holding it while focused charges up a bigger shot instead.
+    let fire_pressed = keyboard.just_pressed(KeyCode::Space) || fire_button;
+    let fire_held = keyboard.pressed(KeyCode::Space) || fire_held;
+    if fire_pressed || (fire_held && !player.focused) {
+        event_sender.send(WeaponFireEvent {
+            entity,
+            slot: WeaponSlot::Primary,
+        });
+    } else if fire_held {
+        charge_sender.send(WeaponChargeEvent(entity));
+    }
+    if keyboard.pressed(KeyCode::KeyC) || secondary_button {
+        event_sender.send(WeaponFireEvent {
+            entity,
+            slot: WeaponSlot::Secondary,
+        });
+    }
+    if keyboard.just_pressed(KeyCode::KeyB) || bomb_button {
+        bomb_sender.send(SmartBombEvent(entity));
     }
 }
 
@@ -176,5 +446,28 @@ fn spawn_player(
         ..default()
     };
 
-    commands.spawn(player);
+    commands.spawn(player).with_children(|parent| {
+        parent.spawn((
+            Sprite::from_color(Color::srgba(1.0, 1.0, 1.0, 0.3), PLAYER_HITBOX * 2.0),
+            Transform::from_xyz(0.0, 0.0, 0.1),
+            Visibility::Hidden,
+            HitboxMarker,
+        ));
+        parent.spawn((
+            Sprite::from_color(Color::WHITE, vec2(4.0, 4.0)),
+            Transform::from_xyz(0.0, 0.0, 0.2),
+            Visibility::Hidden,
+            HitboxMarker,
+        ));
+        parent.spawn((
+            Sprite {
+                custom_size: Some(vec2(0.0, 3.0)),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            Transform::from_xyz(-CHARGE_METER_WIDTH / 2.0, -22.0, 0.2),
+            Visibility::Hidden,
+            ChargeMeter,
+        ));
+    });
 }
@@ -0,0 +1,125 @@
+//! Reusing entities instead of spawning and despawning them.
+//!
+//! Dense bullet patterns create and destroy thousands of projectiles a
+//! second. A `Pool` keeps released entities around, hidden, and hands them
+//! back out for the next spawn.
+
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// Entities that have carried a `T`, and are kept hidden while they wait
+/// to be reused.
+///
+/// Released entities keep all their components, so handing them out again
+/// only overwrites the values, and never moves them to another archetype.
+#[derive(Resource)]
+pub struct Pool<T: Component> {
+    free: Vec<Entity>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component> Default for Pool<T> {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Marks entities that belong to a pool.
+///
+/// Queries over pooled components should skip entities that aren't active.
+#[derive(Component)]
+pub struct Pooled {
+    active: bool,
+}
+
+impl Pooled {
+    /// True while the entity is in use.
+    pub fn active(&self) -> bool {
+        self.active
+    }
+}
+
+impl<T: Component> Pool<T> {
+    /// Spawn `bundle`, on a free entity if there is one.
+    ///
+    /// The bundle should include the `T`, and replace every component the
+    /// entity had the last time it was used.
+    pub fn spawn(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        let active = Pooled { active: true };
+        match self.free.pop() {
+            Some(entity) => {
+                commands
+                    .entity(entity)
+                    .insert((bundle, active, Visibility::Inherited));
+                entity
+            }
+            None => commands.spawn((bundle, active)).id(),
+        }
+    }
+
+    /// Put an entity back in the pool.
+    ///
+    /// This is safe to call more than once for the same entity.
+    pub fn release(commands: &mut Commands, entity: Entity) {
+        commands.queue(move |world: &mut World| {
+            let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+                return;
+            };
+            let Some(mut pooled) = entity_mut.get_mut::<Pooled>() else {
+                return;
+            };
+            if !pooled.active {
+                return;
+            }
+            pooled.active = false;
+            if let Some(mut visibility) = entity_mut.get_mut::<Visibility>() {
+                *visibility = Visibility::Hidden;
+            }
+            world.resource_mut::<Pool<T>>().free.push(entity);
+        });
+    }
+
+    /// Set aside some entities ahead of time.
+    ///
+    /// These only get the rest of their components the first time they're
+    /// used.
+    pub fn prewarm(&mut self, commands: &mut Commands, count: usize) {
+        for _ in 0..count {
+            let entity = commands
+                .spawn((Pooled { active: false }, Visibility::Hidden))
+                .id();
+            self.free.push(entity);
+        }
+    }
+}
+
+/// Sets up a `Pool<T>`, with some entities ready to go.
+pub struct PoolPlugin<T> {
+    prewarm: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T> PoolPlugin<T> {
+    pub fn new(prewarm: usize) -> Self {
+        Self {
+            prewarm,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: Component> Plugin for PoolPlugin<T> {
+    fn build(&self, app: &mut App) {
+        let prewarm = self.prewarm;
+        app.insert_resource(Pool::<T>::default()).add_systems(
+            Startup,
+            move |mut commands: Commands, mut pool: ResMut<Pool<T>>| {
+                pool.prewarm(&mut commands, prewarm);
+            },
+        );
+    }
+}
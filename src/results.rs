@@ -0,0 +1,227 @@
+//! The results screen shown between levels.
+
+use bevy::prelude::*;
+
+use crate::collide::{EnemyDeathEvent, PlayerDeathEvent, PlayerHitEvent};
+use crate::difficulty::Difficulty;
+use crate::level::CurrentLevel;
+use crate::pool::Pooled;
+use crate::ui::{Score, UiAssets};
+use crate::weapon::Projectile;
+use crate::GameState;
+
+/// Seconds the results stay on screen.
+pub const RESULTS_SECONDS: f32 = 6.0;
+/// Bonus points counted into the score per second.
+const COUNT_UP_RATE: f32 = 3000.0;
+const NO_DEATH_BONUS: u32 = 2000;
+/// Bonus for hitting with every shot.
+const ACCURACY_BONUS: f32 = 2000.0;
+/// Seconds allowed per enemy before the speed bonus runs out.
+const PAR_SECONDS_PER_ENEMY: f32 = 2.0;
+/// Speed bonus points per second under par.
+const SPEED_BONUS_RATE: f32 = 20.0;
+
+/// What the player did during a level.
+#[derive(Debug, Default, Clone)]
+pub struct LevelStats {
+    pub shots_fired: u32,
+    pub hits: u32,
+    pub enemies_destroyed: u32,
+    /// Seconds spent playing the level, including restarts.
+    pub time: f32,
+    pub damage_taken: u32,
+    pub deaths: u32,
+}
+
+impl LevelStats {
+    /// The fraction of shots that hit something.
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            return 0.0;
+        }
+        (self.hits as f32 / self.shots_fired as f32).min(1.0)
+    }
+
+    fn no_death_bonus(&self) -> u32 {
+        if self.deaths == 0 {
+            NO_DEATH_BONUS
+        } else {
+            0
+        }
+    }
+
+    fn speed_bonus(&self) -> u32 {
+        let par = self.enemies_destroyed as f32 * PAR_SECONDS_PER_ENEMY;
+        round_bonus((par - self.time).max(0.0) * SPEED_BONUS_RATE)
+    }
+
+    fn accuracy_bonus(&self) -> u32 {
+        round_bonus(self.accuracy() * ACCURACY_BONUS)
+    }
+}
+
+/// Round bonus points down to a multiple of 10.
+fn round_bonus(points: f32) -> u32 {
+    points as u32 / 10 * 10
+}
+
+pub struct ResultsPlugin;
+
+impl Plugin for ResultsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShowResultsEvent>().add_systems(
+            Update,
+            (
+                count_shots,
+                count_kills,
+                count_damage,
+                count_deaths,
+                time_level,
+                show_results,
+                count_up_bonus,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Show the results for a finished level.
+#[derive(Event)]
+pub struct ShowResultsEvent {
+    pub level: usize,
+    pub stats: LevelStats,
+}
+
+/// The results text, while the bonus is counted into the score.
+#[derive(Component)]
+struct Results {
+    /// Everything above the bonus count.
+    text: String,
+    /// Bonus points not yet added to the score.
+    bonus: u32,
+    counted: u32,
+    timer: Timer,
+}
+
+fn count_shots(
+    projectiles: Query<(&Projectile, &Pooled), Changed<Pooled>>,
+    mut current_level: ResMut<CurrentLevel>,
+) {
+    // Reused projectiles aren't added again, but they are switched back on.
+    for (projectile, pooled) in &projectiles {
+        if pooled.active() && projectile.player {
+            current_level.stats.shots_fired += 1;
+        }
+    }
+}
+
+fn count_kills(mut event: EventReader<EnemyDeathEvent>, mut current_level: ResMut<CurrentLevel>) {
+    current_level.stats.enemies_destroyed += event.read().count() as u32;
+}
+
+fn count_damage(mut event: EventReader<PlayerHitEvent>, mut current_level: ResMut<CurrentLevel>) {
+    for event in event.read() {
+        current_level.stats.damage_taken += event.damage as u32;
+    }
+}
+
+fn count_deaths(mut event: EventReader<PlayerDeathEvent>, mut current_level: ResMut<CurrentLevel>) {
+    if event.read().last().is_some() {
+        current_level.stats.deaths += 1;
+    }
+}
+
+/// Count the time while the level is running.
+fn time_level(time: Res<Time<Virtual>>, mut current_level: ResMut<CurrentLevel>) {
+    if current_level.level_start_timer.finished() {
+        current_level.stats.time += time.delta_secs();
+    }
+}
+
+fn show_results(
+    mut commands: Commands,
+    mut event: EventReader<ShowResultsEvent>,
+    assets: Res<UiAssets>,
+    difficulty: Res<Difficulty>,
+) {
+    let Some(ShowResultsEvent { level, stats }) = event.read().last() else {
+        return;
+    };
+    info!("level {level} results {stats:?}");
+
+    let no_death = difficulty.score(stats.no_death_bonus());
+    let speed = difficulty.score(stats.speed_bonus());
+    let accuracy = difficulty.score(stats.accuracy_bonus());
+    let text = format!(
+        "LEVEL {level} COMPLETE\n\n\
+         SHOTS FIRED  {:>6}\n\
+         HITS         {:>6}\n\
+         ACCURACY     {:>5}%\n\
+         DESTROYED    {:>6}\n\
+         TIME         {:>5}s\n\
+         DAMAGE TAKEN {:>6}\n\n\
+         NO DEATH     {no_death:>6}\n\
+         SPEED        {speed:>6}\n\
+         ACCURACY     {accuracy:>6}",
+        stats.shots_fired,
+        stats.hits,
+        (stats.accuracy() * 100.0).round(),
+        stats.enemies_destroyed,
+        stats.time.round(),
+        stats.damage_taken,
+    );
+
+    commands.spawn((
+        Text2d::new(text.clone()),
+        TextFont {
+            font: assets.font.clone_weak(),
+            font_size: 16.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        Results {
+            text,
+            bonus: no_death + speed + accuracy,
+            counted: 0,
+            timer: Timer::from_seconds(RESULTS_SECONDS - 0.5, TimerMode::Once),
+        },
+    ));
+}
+
+/// Count the bonus into the score, then take down the results.
+fn count_up_bonus(
+    mut commands: Commands,
+    time: Res<Time<Virtual>>,
+    mut score: ResMut<Score>,
+    mut query: Query<(&mut Results, &mut Text2d, Entity)>,
+) {
+    use std::fmt::Write;
+
+    for (mut results, mut text, entity) in &mut query {
+        results.timer.tick(time.delta());
+
+        let step = if results.timer.finished() {
+            results.bonus
+        } else {
+            ((COUNT_UP_RATE * time.delta_secs()) as u32).clamp(1, results.bonus.max(1))
+        };
+        let step = step.min(results.bonus);
+        results.bonus -= step;
+        results.counted += step;
+        score.0 += step;
+
+        let results_string = &mut text.0;
+        results_string.clear();
+        write!(
+            results_string,
+            "{}\n\nBONUS        {:>6}",
+            results.text, results.counted
+        )
+        .unwrap();
+
+        if results.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -1,12 +1,28 @@
+use std::cmp::Reverse;
 use std::ops::{Deref, DerefMut};
 
 use bevy::math::vec3;
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 use bevy::winit::WinitWindows;
 
+use crate::bomb::SmartBombs;
+use crate::collide::PlayerHitEvent;
+use crate::difficulty::Difficulty;
+use crate::health::HealthModel;
 use crate::level::CurrentLevel;
+use crate::player::{Dash, Player};
+use crate::weapon::{SecondaryWeapon, WeaponTier, MAX_TIER};
 use crate::GameState;
 
+/// How many high scores to keep.
+const MAX_HIGH_SCORES: usize = 5;
+/// Seconds allowed between kills to keep a combo going.
+const COMBO_WINDOW: f32 = 2.0;
+/// Kills needed for each step up in the combo multiplier.
+const COMBO_STEP: u32 = 5;
+const MAX_COMBO_MULTIPLIER: u32 = 8;
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
@@ -15,20 +31,51 @@ impl Plugin for UiPlugin {
             .add_event::<GameOverEvent>()
             .init_state::<IconState>()
             .insert_resource(Score::default())
+            .insert_resource(Combo::default())
+            .insert_resource(NextExtraLife::default())
             .insert_resource(PlayerLives::default())
+            .insert_resource(Difficulty::default())
+            .insert_resource(HighScores::default())
             .add_systems(PreStartup, UiAssets::load)
-            .add_systems(Startup, (create_score, create_intro_text))
+            .add_systems(
+                Startup,
+                (
+                    create_score,
+                    create_combo,
+                    create_bombs,
+                    create_dash,
+                    create_power,
+                    create_missiles,
+                    create_intro_text,
+                ),
+            )
             .add_systems(
                 Update,
                 set_window_icon.run_if(in_state(IconState::NotLoaded)),
             )
-            .add_systems(Update, start_game.run_if(in_state(GameState::Idle)))
+            .add_systems(
+                Update,
+                (select_difficulty, start_game).run_if(in_state(GameState::Idle)),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_lives,
+                    update_bombs,
+                    update_dash,
+                    update_power,
+                    update_missiles,
+                ),
+            )
             .add_systems(
                 Update,
                 (
                     show_level_text,
                     autohide_text,
                     update_score,
+                    update_combo,
+                    reset_combo,
+                    award_extra_lives,
                     pause_game,
                     game_over,
                 )
@@ -38,14 +85,22 @@ impl Plugin for UiPlugin {
 }
 
 #[derive(Resource)]
-struct UiAssets {
-    font: Handle<Font>,
+pub struct UiAssets {
+    pub font: Handle<Font>,
+    ship_image: Handle<Image>,
+    extra_life_sound: Handle<AudioSource>,
 }
 
 impl UiAssets {
     fn load(mut commands: Commands, asset_server: Res<AssetServer>) {
         let font = asset_server.load("monoMMM_5.ttf");
-        commands.insert_resource(UiAssets { font });
+        let ship_image = asset_server.load("red_ship.png");
+        let extra_life_sound = asset_server.load("extra_life.wav");
+        commands.insert_resource(UiAssets {
+            font,
+            ship_image,
+            extra_life_sound,
+        });
     }
 }
 
@@ -68,6 +123,32 @@ impl DerefMut for PlayerLives {
 #[derive(Component)]
 pub struct ScoreText;
 
+#[derive(Component)]
+pub struct ComboText;
+
+#[derive(Component)]
+pub struct BombText;
+
+/// Shows whether the dash has recharged.
+#[derive(Component)]
+pub struct DashText;
+
+/// Shows the weapon tier.
+#[derive(Component)]
+pub struct PowerText;
+
+/// Shows the side missiles left.
+#[derive(Component)]
+pub struct MissileText;
+
+/// One of the ship icons showing the remaining lives.
+#[derive(Component)]
+pub struct LifeIcon;
+
+/// The score that earns the next extra life.
+#[derive(Default, Resource)]
+pub struct NextExtraLife(pub u32);
+
 #[derive(Component)]
 pub struct LevelText;
 
@@ -75,6 +156,10 @@ pub struct LevelText;
 #[derive(Component)]
 pub struct InterstitialText;
 
+/// The difficulty selector shown between games.
+#[derive(Component)]
+pub struct DifficultyText;
+
 #[derive(Component)]
 pub struct AutoHide {
     fade: Timer,
@@ -88,27 +173,71 @@ impl Default for AutoHide {
     }
 }
 
-fn create_intro_text(assets: Res<UiAssets>, commands: Commands) {
-    create_interstitial_text("FIRE TO START", assets, commands)
+fn create_intro_text(
+    assets: Res<UiAssets>,
+    difficulty: Res<Difficulty>,
+    health_model: Res<HealthModel>,
+    commands: Commands,
+) {
+    create_interstitial_text(
+        "FIRE TO START",
+        *difficulty,
+        *health_model,
+        assets,
+        commands,
+    )
 }
 
-fn create_gameover_text(assets: Res<UiAssets>, commands: Commands) {
-    create_interstitial_text("GAME OVER", assets, commands)
+fn create_gameover_text(
+    seed: u32,
+    high_scores: &HighScores,
+    difficulty: Difficulty,
+    health_model: HealthModel,
+    assets: Res<UiAssets>,
+    commands: Commands,
+) {
+    use std::fmt::Write;
+
+    // Show the seed, so the endless levels can be replayed.
+    let mut text = format!("GAME OVER\n\nSEED {seed:08X}\n\nHIGH SCORES");
+    for high_score in &high_scores.0 {
+        write!(text, "\n{:06} {}", high_score.score, high_score.difficulty).unwrap();
+    }
+    create_interstitial_text(&text, difficulty, health_model, assets, commands)
 }
 
-fn create_interstitial_text(text: &str, assets: Res<UiAssets>, mut commands: Commands) {
+fn create_interstitial_text(
+    text: &str,
+    difficulty: Difficulty,
+    health_model: HealthModel,
+    assets: Res<UiAssets>,
+    mut commands: Commands,
+) {
+    let font = TextFont {
+        font: assets.font.clone_weak(),
+        font_size: 20.0,
+        ..default()
+    };
     commands.spawn((
         Text2d::new(text),
-        TextFont {
-            font: assets.font.clone_weak(),
-            font_size: 20.0,
-            ..default()
-        },
+        font.clone(),
+        TextLayout::new_with_justify(JustifyText::Center),
+        InterstitialText,
+    ));
+    commands.spawn((
+        Text2d::new(difficulty_text(difficulty, health_model)),
+        font,
         TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_translation(vec3(0.0, -150.0, 0.0)),
         InterstitialText,
+        DifficultyText,
     ));
 }
 
+fn difficulty_text(difficulty: Difficulty, health_model: HealthModel) -> String {
+    format!("< {difficulty} >\n\n{health_model} RULES")
+}
+
 #[derive(Event)]
 pub struct ShowLevelEvent(pub String);
 
@@ -157,6 +286,23 @@ fn autohide_text(
 #[derive(Default, Resource)]
 pub struct Score(pub u32);
 
+struct HighScore {
+    score: u32,
+    difficulty: Difficulty,
+}
+
+/// The best scores from this session, highest first.
+#[derive(Default, Resource)]
+struct HighScores(Vec<HighScore>);
+
+impl HighScores {
+    fn record(&mut self, score: u32, difficulty: Difficulty) {
+        self.0.push(HighScore { score, difficulty });
+        self.0.sort_by_key(|high_score| Reverse(high_score.score));
+        self.0.truncate(MAX_HIGH_SCORES);
+    }
+}
+
 fn create_score(assets: Res<UiAssets>, mut commands: Commands) {
     commands.spawn((
         Text2d::new("------"),
@@ -180,6 +326,233 @@ fn update_score(score: Res<Score>, mut query: Query<&mut Text2d, With<ScoreText>
     write!(score_string, "{:06}", score.0).unwrap();
 }
 
+/// Show one ship icon per remaining life.
+fn update_lives(
+    mut commands: Commands,
+    lives: Res<PlayerLives>,
+    assets: Res<UiAssets>,
+    icons: Query<Entity, With<LifeIcon>>,
+) {
+    if !lives.is_changed() {
+        return;
+    }
+    for entity in &icons {
+        commands.entity(entity).despawn();
+    }
+    for n in 0..**lives {
+        commands.spawn((
+            Sprite::from_image(assets.ship_image.clone_weak()),
+            Transform::from_translation(vec3(-180.0 + 22.0 * n as f32, 380.0, -1.0))
+                .with_scale(Vec3::splat(0.5)),
+            LifeIcon,
+        ));
+    }
+}
+
+/// A line of small text in the HUD, under the lives.
+fn hud_text(assets: &UiAssets, y: f32, marker: impl Component) -> impl Bundle {
+    (
+        Text2d::new(""),
+        TextFont {
+            font: assets.font.clone_weak(),
+            font_size: 14.0,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Left),
+        Anchor::CenterLeft,
+        Transform::from_translation(vec3(-190.0, y, -1.0)),
+        marker,
+    )
+}
+
+fn create_bombs(assets: Res<UiAssets>, mut commands: Commands) {
+    commands.spawn(hud_text(&assets, 358.0, BombText));
+}
+
+fn update_bombs(bombs: Res<SmartBombs>, mut query: Query<&mut Text2d, With<BombText>>) {
+    if !bombs.is_changed() {
+        return;
+    }
+    let mut bomb_text = query.single_mut();
+    bomb_text.0 = format!("BOMBS {}", bombs.0);
+}
+
+fn create_power(assets: Res<UiAssets>, mut commands: Commands) {
+    commands.spawn(hud_text(&assets, 326.0, PowerText));
+}
+
+fn update_power(weapon_tier: Res<WeaponTier>, mut query: Query<&mut Text2d, With<PowerText>>) {
+    if !weapon_tier.is_changed() {
+        return;
+    }
+    let mut power_text = query.single_mut();
+    power_text.0 = if weapon_tier.0 >= MAX_TIER {
+        "POWER MAX".to_string()
+    } else {
+        format!("POWER {}", weapon_tier.0)
+    };
+}
+
+fn create_missiles(assets: Res<UiAssets>, mut commands: Commands) {
+    commands.spawn((hud_text(&assets, 310.0, MissileText), Visibility::Hidden));
+}
+
+/// Show the missile count, dimmed while reloading.
+fn update_missiles(
+    players: Query<&SecondaryWeapon, With<Player>>,
+    mut query: Query<(&mut Text2d, &mut TextColor, &mut Visibility), With<MissileText>>,
+) {
+    let (mut text, mut color, mut visibility) = query.single_mut();
+    let Ok(secondary) = players.get_single() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    let missile_text = format!("MISSILES {}", secondary.ammo);
+    // Avoid triggering change detection every frame.
+    if text.0 != missile_text {
+        text.0 = missile_text;
+    }
+    color.0 = if secondary.ready() {
+        Color::WHITE
+    } else {
+        Color::srgb(0.4, 0.4, 0.4)
+    };
+}
+
+fn create_dash(assets: Res<UiAssets>, mut commands: Commands) {
+    commands
+        .spawn((hud_text(&assets, 342.0, DashText), Visibility::Hidden))
+        .insert(Text2d::new("DASH"));
+}
+
+/// Dim the dash indicator while it recharges.
+fn update_dash(
+    players: Query<&Dash, With<Player>>,
+    mut query: Query<(&mut TextColor, &mut Visibility), With<DashText>>,
+) {
+    let (mut color, mut visibility) = query.single_mut();
+    let Ok(dash) = players.get_single() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    color.0 = if dash.charge() >= 1.0 {
+        Color::srgb(0.4, 1.0, 0.4)
+    } else {
+        Color::srgba(1.0, 1.0, 1.0, 0.2 + 0.5 * dash.charge())
+    };
+}
+
+/// Give the player another life each time the score passes a threshold.
+fn award_extra_lives(
+    mut commands: Commands,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    assets: Res<UiAssets>,
+    mut lives: ResMut<PlayerLives>,
+    mut next: ResMut<NextExtraLife>,
+    mut text: EventWriter<ShowLevelEvent>,
+) {
+    if next.0 == 0 || score.0 < next.0 {
+        return;
+    }
+    while score.0 >= next.0 {
+        next.0 += difficulty.settings().extra_life_every;
+        **lives += 1;
+    }
+    info!("extra life ({} lives)", **lives);
+    text.send(ShowLevelEvent("EXTRA LIFE".to_string()));
+    commands.spawn((
+        AudioPlayer(assets.extra_life_sound.clone_weak()),
+        PlaybackSettings::DESPAWN,
+    ));
+}
+
+/// Kills in quick succession, which multiply the points for each kill.
+#[derive(Resource)]
+pub struct Combo {
+    kills: u32,
+    /// Time left before the combo lapses.
+    timer: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        let mut timer = Timer::from_seconds(COMBO_WINDOW, TimerMode::Once);
+        timer.pause();
+        Self { kills: 0, timer }
+    }
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.kills / COMBO_STEP).min(MAX_COMBO_MULTIPLIER)
+    }
+
+    /// Count a kill, and return the points it's worth.
+    pub fn kill(&mut self, points: u32) -> u32 {
+        let points = points * self.multiplier();
+        self.kills += 1;
+        self.timer.reset();
+        self.timer.unpause();
+        points
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn create_combo(assets: Res<UiAssets>, mut commands: Commands) {
+    commands.spawn((
+        Text2d::new(""),
+        TextFont {
+            font: assets.font.clone_weak(),
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Transform::from_translation(vec3(100.0, 360.0, -1.0)),
+        ComboText,
+    ));
+}
+
+/// Let the combo lapse, and show it if there is one.
+fn update_combo(
+    time: Res<Time<Virtual>>,
+    mut combo: ResMut<Combo>,
+    mut query: Query<&mut Text2d, With<ComboText>>,
+) {
+    use std::fmt::Write;
+
+    combo.timer.tick(time.delta());
+    if combo.timer.just_finished() {
+        combo.reset();
+    }
+
+    let mut combo_text = query.single_mut();
+    let combo_string = &mut combo_text.0;
+    combo_string.clear();
+    if combo.kills > 1 {
+        write!(
+            combo_string,
+            "COMBO {} x{}",
+            combo.kills,
+            combo.multiplier()
+        )
+        .unwrap();
+    }
+}
+
+/// Getting hit breaks the combo, even if the shield takes it.
+fn reset_combo(mut event: EventReader<PlayerHitEvent>, mut combo: ResMut<Combo>) {
+    if event.read().last().is_some() {
+        combo.reset();
+    }
+}
+
 fn pause_game(
     mut time: ResMut<Time<Virtual>>,
     keyboard: Res<ButtonInput<KeyCode>>,
@@ -196,6 +569,40 @@ fn pause_game(
     }
 }
 
+/// Pick the difficulty with left and right, and the health rules with up and down.
+fn select_difficulty(
+    gamepads: Query<&Gamepad>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut health_model: ResMut<HealthModel>,
+    mut query: Query<&mut Text2d, With<DifficultyText>>,
+) {
+    let mut left = keyboard.just_pressed(KeyCode::ArrowLeft);
+    let mut right = keyboard.just_pressed(KeyCode::ArrowRight);
+    let mut up_down =
+        keyboard.just_pressed(KeyCode::ArrowUp) || keyboard.just_pressed(KeyCode::ArrowDown);
+    if let Some(gamepad) = gamepads.iter().next() {
+        left |= gamepad.digital().just_pressed(GamepadButton::DPadLeft);
+        right |= gamepad.digital().just_pressed(GamepadButton::DPadRight);
+        up_down |= gamepad.digital().just_pressed(GamepadButton::DPadUp);
+        up_down |= gamepad.digital().just_pressed(GamepadButton::DPadDown);
+    }
+
+    if left {
+        *difficulty = difficulty.easier();
+    } else if right {
+        *difficulty = difficulty.harder();
+    } else if up_down {
+        *health_model = health_model.toggle();
+    } else {
+        return;
+    }
+
+    for mut text in &mut query {
+        text.0 = difficulty_text(*difficulty, *health_model);
+    }
+}
+
 #[expect(clippy::too_many_arguments)]
 fn start_game(
     gamepads: Query<&Gamepad>,
@@ -203,7 +610,10 @@ fn start_game(
     mut next_state: ResMut<NextState<GameState>>,
     mut lives: ResMut<PlayerLives>,
     mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    mut next_extra_life: ResMut<NextExtraLife>,
     mut current_level: ResMut<CurrentLevel>,
+    difficulty: Res<Difficulty>,
     start_text: Query<Entity, With<InterstitialText>>,
     mut commands: Commands,
 ) {
@@ -213,15 +623,17 @@ fn start_game(
     }
 
     if fire_pressed {
-        info!("start game");
+        info!("start game ({})", *difficulty);
         next_state.set(GameState::Playing);
-        lives.0 = 3;
+        lives.0 = difficulty.settings().lives;
         score.0 = 0;
+        combo.reset();
+        next_extra_life.0 = difficulty.settings().extra_life_every;
         // FIXME: is there a better way to do this?
         *current_level = default();
 
         // If the interstitial text is still onscreen make it go away.
-        if let Ok(entity) = start_text.get_single() {
+        for entity in &start_text {
             commands.entity(entity).despawn();
         }
     }
@@ -230,9 +642,15 @@ fn start_game(
 #[derive(Event)]
 pub struct GameOverEvent;
 
+#[expect(clippy::too_many_arguments)]
 fn game_over(
     mut event: EventReader<GameOverEvent>,
     mut next_state: ResMut<NextState<GameState>>,
+    current_level: Res<CurrentLevel>,
+    score: Res<Score>,
+    difficulty: Res<Difficulty>,
+    health_model: Res<HealthModel>,
+    mut high_scores: ResMut<HighScores>,
     assets: Res<UiAssets>,
     commands: Commands,
 ) {
@@ -241,7 +659,15 @@ fn game_over(
     };
 
     info!("game over");
-    create_gameover_text(assets, commands);
+    high_scores.record(score.0, *difficulty);
+    create_gameover_text(
+        current_level.seed,
+        &high_scores,
+        *difficulty,
+        *health_model,
+        assets,
+        commands,
+    );
 
     next_state.set(GameState::Idle);
 }
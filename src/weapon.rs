@@ -1,16 +1,142 @@
+use std::time::Duration;
+
 use bevy::audio::{PlaybackMode, Volume};
+use bevy::math::vec2;
 use bevy::prelude::*;
 
+use crate::collide::PlayerDeathEvent;
+use crate::drone::{follow_player, Drone};
+use crate::enemy::SpawnerResetEvent;
 use crate::player::{player_movement, Player};
+use crate::pool::{Pool, PoolPlugin, Pooled};
+use crate::GameState;
+
+/// Seconds fire must be held in focus before a release fires a charged shot.
+const MIN_CHARGE: f32 = 0.3;
+/// Seconds to reach full charge.
+const FULL_CHARGE: f32 = 1.2;
+const CHARGED_PROJECTILE_VELOCITY: f32 = 600.0;
+/// Enemies a fully charged shot can pass through.
+const CHARGED_PIERCE: u32 = 5;
+/// Seconds a press is remembered while the weapon is recharging.
+const FIRE_BUFFER_SECONDS: f32 = 0.1;
+/// Spread multiplier while the player is focused.
+const FOCUS_SPREAD: f32 = 0.25;
+/// Sideways distance between shots fired together.
+const SHOT_SPACING: f32 = 6.0;
+const MISSILE_VELOCITY: f32 = 350.0;
+/// Sideways speed of the side missiles.
+const MISSILE_DRIFT: f32 = 40.0;
+/// Distance from the middle of the ship to each missile launcher.
+const MISSILE_OFFSET: f32 = 12.0;
+const MISSILE_DAMAGE: u32 = 3;
+/// Seconds to reload the side missiles.
+const MISSILE_RECHARGE: f32 = 0.8;
+/// Distance the side missiles fly before burning out.
+const MISSILE_RANGE: f32 = 450.0;
+/// Missiles carried at the start of each level.
+pub const MISSILE_AMMO: u32 = 20;
+/// Projectile entities to set aside at startup.
+const PROJECTILE_POOL_SIZE: usize = 512;
+
+/// The highest weapon tier.
+pub const MAX_TIER: u32 = 5;
+
+/// How the player weapon fires at one tier.
+struct Tier {
+    /// Projectiles per shot.
+    shots: u32,
+    /// Angle in radians between the outermost projectiles.
+    spread: f32,
+    /// Seconds to recharge after a shot.
+    recharge: f32,
+    /// Damage done to enemies that take more than one hit.
+    damage: u32,
+    /// Projectile tint and size.
+    color: Color,
+    scale: f32,
+}
+
+const TIERS: [Tier; MAX_TIER as usize] = [
+    Tier {
+        shots: 1,
+        spread: 0.0,
+        recharge: 0.25,
+        damage: 1,
+        color: Color::WHITE,
+        scale: 1.0,
+    },
+    Tier {
+        shots: 2,
+        spread: 0.1,
+        recharge: 0.25,
+        damage: 1,
+        color: Color::WHITE,
+        scale: 1.0,
+    },
+    Tier {
+        shots: 3,
+        spread: 0.3,
+        recharge: 0.21,
+        damage: 1,
+        color: Color::srgb(0.6, 1.0, 1.0),
+        scale: 1.0,
+    },
+    Tier {
+        shots: 3,
+        spread: 0.3,
+        recharge: 0.18,
+        damage: 2,
+        color: Color::srgb(1.0, 1.0, 0.4),
+        scale: 1.2,
+    },
+    Tier {
+        shots: 5,
+        spread: 0.5,
+        recharge: 0.15,
+        damage: 2,
+        color: Color::srgb(1.0, 0.6, 0.3),
+        scale: 1.4,
+    },
+];
 
 pub struct WeaponsPlugin;
 
 impl Plugin for WeaponsPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<WeaponFireEvent>()
+            .add_event::<WeaponChargeEvent>()
+            .add_event::<EnemyVolleyEvent>()
+            .insert_resource(WeaponTier::default())
+            .add_plugins(PoolPlugin::<Projectile>::new(PROJECTILE_POOL_SIZE))
             .add_systems(Startup, WeaponAssets::load)
-            .add_systems(Update, (charge_weapons, move_projectiles))
-            .add_systems(Update, fire_weapon.after(player_movement));
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Idle,
+                    entered: GameState::Playing,
+                },
+                reset_tier,
+            )
+            .add_systems(
+                Update,
+                (downgrade_on_death, restock_missiles).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(Update, (charge_weapons, move_projectiles, fire_volley))
+            .add_systems(
+                Update,
+                (
+                    // Drones fire from where they are this frame.
+                    fire_weapon.after(follow_player),
+                    fire_secondary,
+                )
+                    .after(player_movement),
+            )
+            .add_systems(
+                Update,
+                charge_shot
+                    .after(player_movement)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -47,18 +173,99 @@ impl WeaponAssets {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileKind {
+    #[default]
+    Torpedo,
+    /// Slow and large.
+    Bomb,
+    /// A player shot, charged up by holding fire while focused.
+    Charged,
+    /// A player side missile.
+    Missile,
+}
+
+impl ProjectileKind {
+    /// Damage done to a player with shields.
+    pub fn damage(self) -> f32 {
+        match self {
+            ProjectileKind::Torpedo | ProjectileKind::Missile => 25.0,
+            ProjectileKind::Bomb | ProjectileKind::Charged => 40.0,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Projectile {
     pub velocity_vector: Vec2,
     /// Player projectiles can't hurt players; enemy projectiles can't hurt enemies.
     pub player: bool,
+    pub kind: ProjectileKind,
+    /// Damage done to enemies that take more than one hit.
+    pub damage: u32,
+    /// Enemies this can hit before it's used up.
+    pub pierce: u32,
+    /// Enemies already hit by a piercing projectile.
+    pub struck: Vec<Entity>,
+    /// Time left before the projectile disappears.
+    lifetime: Option<Timer>,
+    /// Distance left before the projectile disappears.
+    range: Option<f32>,
+    /// Times the projectile can bounce off the sides of the screen.
+    bounces: u32,
+}
+
+impl Projectile {
+    pub fn new(velocity_vector: Vec2, player: bool, kind: ProjectileKind) -> Self {
+        Self {
+            velocity_vector,
+            player,
+            kind,
+            damage: 1,
+            pierce: 1,
+            struck: Vec::new(),
+            lifetime: None,
+            range: None,
+            bounces: 0,
+        }
+    }
+
+    fn with_damage(mut self, damage: u32) -> Self {
+        self.damage = damage;
+        self
+    }
+
+    fn with_pierce(mut self, pierce: u32) -> Self {
+        self.pierce = pierce;
+        self
+    }
+
+    fn with_lifetime(mut self, seconds: f32) -> Self {
+        self.lifetime = Some(Timer::from_seconds(seconds, TimerMode::Once));
+        self
+    }
+
+    fn with_range(mut self, range: f32) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    fn with_bounces(mut self, bounces: u32) -> Self {
+        self.bounces = bounces;
+        self
+    }
+}
+
+/// Remove a projectile, returning its entity to the pool.
+pub fn despawn_projectile(commands: &mut Commands, entity: Entity) {
+    Pool::<Projectile>::release(commands, entity);
 }
 
 #[derive(Bundle)]
 pub struct ProjectileBundle {
-    projectile: Projectile,
-    sprite: Sprite,
-    transform: Transform,
+    pub projectile: Projectile,
+    pub sprite: Sprite,
+    pub transform: Transform,
 }
 
 #[derive(Component)]
@@ -67,94 +274,467 @@ pub struct Weapon {
     aim_vector: Vec2,
     // Time in seconds to recharge after a shot.
     ready_timer: Timer,
+    // Time in seconds the fire button has been held.
+    charge: f32,
+    // Running while there's a press waiting for the weapon to recharge.
+    buffer: Timer,
 }
 
 impl Weapon {
     pub fn new(aim_vector: Vec2, recharge_time: f32) -> Self {
         let ready_timer = Timer::from_seconds(recharge_time, TimerMode::Once);
+        let mut buffer = Timer::from_seconds(FIRE_BUFFER_SECONDS, TimerMode::Once);
+        buffer.tick(buffer.duration());
         Self {
             aim_vector,
             ready_timer,
+            charge: 0.0,
+            buffer,
+        }
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.ready_timer.tick(delta);
+        self.buffer.tick(delta);
+    }
+
+    /// How far a charged shot has charged, from 0.0 to 1.0.
+    ///
+    /// This stays at 0.0 until the button has been held long enough
+    /// for a release to fire a charged shot.
+    pub fn charge_fraction(&self) -> f32 {
+        if self.charge < MIN_CHARGE {
+            return 0.0;
         }
+        (self.charge / FULL_CHARGE).min(1.0)
     }
 }
 
+/// The player weapon's upgrade tier, from 1 to `MAX_TIER`.
+///
+/// This belongs to the player rather than the ship, so it carries over
+/// when a new ship spawns.
+#[derive(Resource)]
+pub struct WeaponTier(pub u32);
+
+impl Default for WeaponTier {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl WeaponTier {
+    /// Move up a tier. Returns false if already at the top.
+    pub fn upgrade(&mut self) -> bool {
+        if self.0 >= MAX_TIER {
+            return false;
+        }
+        self.0 += 1;
+        true
+    }
+
+    fn tier(&self) -> &'static Tier {
+        &TIERS[(self.0.clamp(1, MAX_TIER) - 1) as usize]
+    }
+}
+
+/// The player's second weapon: a pair of side missiles, with limited ammo.
+#[derive(Component)]
+pub struct SecondaryWeapon {
+    weapon: Weapon,
+    pub ammo: u32,
+}
+
+impl Default for SecondaryWeapon {
+    fn default() -> Self {
+        Self {
+            weapon: Weapon::new(vec2(0.0, MISSILE_VELOCITY), MISSILE_RECHARGE),
+            ammo: MISSILE_AMMO,
+        }
+    }
+}
+
+impl SecondaryWeapon {
+    /// Whether the missiles are ready to fire.
+    pub fn ready(&self) -> bool {
+        self.ammo > 0 && self.weapon.ready_timer.finished()
+    }
+}
+
+/// Which of an entity's weapons to fire.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WeaponSlot {
+    #[default]
+    Primary,
+    /// The `SecondaryWeapon`.
+    Secondary,
+}
+
+#[derive(Event)]
+pub struct WeaponFireEvent {
+    pub entity: Entity,
+    pub slot: WeaponSlot,
+}
+
+/// The fire button is being held down while focused.
+///
+/// Holding fire without focus autofires instead.
 #[derive(Event)]
-pub struct WeaponFireEvent(pub Entity);
+pub struct WeaponChargeEvent(pub Entity);
+
+/// Fire a group of enemy projectiles from one place.
+#[derive(Event)]
+pub struct EnemyVolleyEvent {
+    pub origin: Vec3,
+    pub kind: ProjectileKind,
+    /// The velocity of each projectile.
+    pub velocities: Vec<Vec2>,
+}
 
 /// Handle the `WeaponFireEvent`
+///
+/// A press that arrives while the weapon is recharging is buffered for a
+/// moment, and fires as soon as the weapon is ready.
 fn fire_weapon(
     mut commands: Commands,
     mut event: EventReader<WeaponFireEvent>,
-    mut query: Query<(&mut Weapon, &Transform, Option<&Player>)>,
+    mut query: Query<(&mut Weapon, &Transform, &Player)>,
+    drones: Query<&Transform, With<Drone>>,
     assets: Res<WeaponAssets>,
+    mut pool: ResMut<Pool<Projectile>>,
+    weapon_tier: Res<WeaponTier>,
 ) {
-    // Ignore multiple fire events.
     for event in event.read() {
-        let (mut weapon, transform, player) =
-            query.get_mut(event.0).expect("missing weapon in entity");
+        if event.slot != WeaponSlot::Primary {
+            continue;
+        }
+        // The ship may have been destroyed since the event was sent.
+        let Ok((mut weapon, _, _)) = query.get_mut(event.entity) else {
+            continue;
+        };
+        weapon.buffer.reset();
+    }
+
+    for (mut weapon, transform, player) in &mut query {
+        if weapon.buffer.finished() || !weapon.ready_timer.finished() {
+            continue;
+        }
+        let duration = weapon.buffer.duration();
+        weapon.buffer.tick(duration);
 
-        let timer = &mut weapon.ready_timer;
-        if timer.finished() {
-            timer.reset();
+        let tier = weapon_tier.tier();
+        weapon
+            .ready_timer
+            .set_duration(Duration::from_secs_f32(tier.recharge));
+        weapon.ready_timer.reset();
+
+        let spread = if player.focused {
+            tier.spread * FOCUS_SPREAD
         } else {
-            // weapon is still charging, do nothing.
-            return;
+            tier.spread
+        };
+        // Each drone fires a copy of the volley.
+        let origins = std::iter::once(transform.translation)
+            .chain(drones.iter().map(|drone| drone.translation));
+        for origin in origins {
+            fire_tier_volley(
+                &mut commands,
+                &mut pool,
+                &assets,
+                tier,
+                weapon.aim_vector,
+                spread,
+                origin,
+            );
         }
+        assets.weapon_audio(&mut commands);
+    }
+}
 
-        let sprite = if player.is_some() {
-            let image = assets.player_projectile_image.clone_weak();
-            Sprite { image, ..default() }
+/// Spawn one volley of player shots at the given tier.
+fn fire_tier_volley(
+    commands: &mut Commands,
+    pool: &mut Pool<Projectile>,
+    assets: &WeaponAssets,
+    tier: &Tier,
+    aim_vector: Vec2,
+    spread: f32,
+    origin: Vec3,
+) {
+    for n in 0..tier.shots {
+        // Spread the shots evenly, from -0.5 on the left to 0.5 on the right.
+        let offset = if tier.shots > 1 {
+            n as f32 / (tier.shots - 1) as f32 - 0.5
         } else {
-            let image = assets.enemy_projectile_image.clone_weak();
-            Sprite {
-                flip_y: true,
-                image,
-                ..default()
-            }
+            0.0
         };
+        let velocity_vector = Vec2::from_angle(-offset * spread).rotate(aim_vector);
+        let projectile = Projectile::new(velocity_vector, true, ProjectileKind::Torpedo)
+            .with_damage(tier.damage);
 
-        let projectile = Projectile {
-            velocity_vector: weapon.aim_vector,
-            player: player.is_some(),
+        let sprite = Sprite {
+            image: assets.player_projectile_image.clone_weak(),
+            color: tier.color,
+            ..default()
         };
-        let bundle = ProjectileBundle {
-            projectile,
-            sprite,
-            transform: *transform,
+        let position = origin + Vec3::X * offset * SHOT_SPACING * (tier.shots - 1) as f32;
+        let transform = Transform::from_translation(position)
+            .with_rotation(Quat::from_rotation_z(-offset * spread))
+            .with_scale(Vec3::splat(tier.scale));
+        pool.spawn(
+            commands,
+            ProjectileBundle {
+                projectile,
+                sprite,
+                transform,
+            },
+        );
+    }
+}
+
+/// Handle the `WeaponFireEvent` for the secondary slot.
+fn fire_secondary(
+    mut commands: Commands,
+    mut event: EventReader<WeaponFireEvent>,
+    mut query: Query<(&mut SecondaryWeapon, &Transform)>,
+    assets: Res<WeaponAssets>,
+    mut pool: ResMut<Pool<Projectile>>,
+) {
+    for event in event.read() {
+        if event.slot != WeaponSlot::Secondary {
+            continue;
+        }
+        let Ok((mut secondary, _)) = query.get_mut(event.entity) else {
+            continue;
         };
+        secondary.weapon.buffer.reset();
+    }
+
+    for (mut secondary, transform) in &mut query {
+        let weapon = &mut secondary.weapon;
+        if weapon.buffer.finished() || !weapon.ready_timer.finished() {
+            continue;
+        }
+        let duration = weapon.buffer.duration();
+        weapon.buffer.tick(duration);
+        if secondary.ammo == 0 {
+            continue;
+        }
+        secondary.weapon.ready_timer.reset();
+        secondary.ammo -= 1;
+
+        // One missile from each side, drifting outwards.
+        for side in [-1.0, 1.0] {
+            let velocity_vector = vec2(side * MISSILE_DRIFT, MISSILE_VELOCITY);
+            // Missiles that drift into a wall bounce back in.
+            let projectile = Projectile::new(velocity_vector, true, ProjectileKind::Missile)
+                .with_damage(MISSILE_DAMAGE)
+                .with_range(MISSILE_RANGE)
+                .with_bounces(1);
+            let sprite = Sprite {
+                image: assets.player_projectile_image.clone_weak(),
+                color: Color::srgb(1.0, 0.4, 0.4),
+                ..default()
+            };
+            let position = transform.translation + Vec3::X * side * MISSILE_OFFSET;
+            let rotation = Quat::from_rotation_arc_2d(Vec2::Y, velocity_vector.normalize());
+            pool.spawn(
+                &mut commands,
+                ProjectileBundle {
+                    projectile,
+                    sprite,
+                    transform: Transform::from_translation(position)
+                        .with_rotation(rotation)
+                        .with_scale(Vec3::splat(1.3)),
+                },
+            );
+        }
+        assets.weapon_audio(&mut commands);
+    }
+}
+
+/// Handle the `EnemyVolleyEvent`
+fn fire_volley(
+    mut commands: Commands,
+    mut event: EventReader<EnemyVolleyEvent>,
+    assets: Res<WeaponAssets>,
+    mut pool: ResMut<Pool<Projectile>>,
+) {
+    for volley in event.read() {
+        for &velocity_vector in &volley.velocities {
+            let sprite = match volley.kind {
+                ProjectileKind::Torpedo | ProjectileKind::Charged | ProjectileKind::Missile => {
+                    Sprite {
+                        flip_y: true,
+                        image: assets.enemy_projectile_image.clone_weak(),
+                        ..default()
+                    }
+                }
+                ProjectileKind::Bomb => Sprite {
+                    image: assets.enemy_projectile_image.clone_weak(),
+                    color: Color::srgb(1.0, 0.6, 0.2),
+                    custom_size: Some(Vec2::splat(12.0)),
+                    ..default()
+                },
+            };
+            // The sprite points down; turn it to face the direction of travel.
+            let direction = velocity_vector.try_normalize().unwrap_or(Vec2::NEG_Y);
+            let rotation = Quat::from_rotation_arc_2d(Vec2::NEG_Y, direction);
+            let transform = Transform::from_translation(volley.origin).with_rotation(rotation);
+
+            pool.spawn(
+                &mut commands,
+                ProjectileBundle {
+                    projectile: Projectile::new(velocity_vector, false, volley.kind),
+                    sprite,
+                    transform,
+                },
+            );
+        }
+        if !volley.velocities.is_empty() {
+            assets.weapon_audio(&mut commands);
+        }
+    }
+}
 
-        commands.spawn(bundle);
+/// Charge weapons while fire is held in focus, and fire a charged shot
+/// when it's released.
+fn charge_shot(
+    mut commands: Commands,
+    mut event: EventReader<WeaponChargeEvent>,
+    mut query: Query<(&mut Weapon, &Transform, Entity)>,
+    assets: Res<WeaponAssets>,
+    mut pool: ResMut<Pool<Projectile>>,
+    time: Res<Time<Virtual>>,
+) {
+    let held: Vec<Entity> = event.read().map(|event| event.0).collect();
+    for (mut weapon, transform, entity) in &mut query {
+        if held.contains(&entity) {
+            weapon.charge += time.delta_secs();
+            continue;
+        }
+        let power = weapon.charge_fraction();
+        weapon.charge = 0.0;
+        if power == 0.0 {
+            continue;
+        }
+
+        info!("charged shot {power:.2}");
+        // A stronger charge hits harder, goes through more enemies and
+        // lasts longer.
+        let projectile = Projectile::new(
+            vec2(0.0, CHARGED_PROJECTILE_VELOCITY),
+            true,
+            ProjectileKind::Charged,
+        )
+        .with_damage(2 + (3.0 * power).round() as u32)
+        .with_pierce(1 + (power * (CHARGED_PIERCE - 1) as f32).round() as u32)
+        .with_lifetime(0.5 + power);
+        let sprite = Sprite {
+            image: assets.player_projectile_image.clone_weak(),
+            color: Color::srgb(0.5, 1.0, 1.0),
+            ..default()
+        };
+        pool.spawn(
+            &mut commands,
+            ProjectileBundle {
+                projectile,
+                sprite,
+                transform: transform.with_scale(Vec3::splat(1.5 + power)),
+            },
+        );
         assets.weapon_audio(&mut commands);
     }
 }
 
+/// Start each game with the basic weapon.
+fn reset_tier(mut weapon_tier: ResMut<WeaponTier>) {
+    weapon_tier.0 = 1;
+}
+
+/// Losing a ship costs one weapon tier.
+fn downgrade_on_death(
+    mut event: EventReader<PlayerDeathEvent>,
+    mut weapon_tier: ResMut<WeaponTier>,
+) {
+    if event.read().last().is_some() && weapon_tier.0 > 1 {
+        weapon_tier.0 -= 1;
+        info!("weapon downgraded to tier {}", weapon_tier.0);
+    }
+}
+
+/// Refill the side missiles when a level starts.
+fn restock_missiles(
+    mut event: EventReader<SpawnerResetEvent>,
+    mut query: Query<&mut SecondaryWeapon>,
+) {
+    if event.read().last().is_some() {
+        for mut secondary in &mut query {
+            secondary.ammo = secondary.ammo.max(MISSILE_AMMO);
+        }
+    }
+}
+
 /// Advance time in weapons timers.
-fn charge_weapons(mut query: Query<&mut Weapon>, time: Res<Time>) {
+fn charge_weapons(
+    mut query: Query<&mut Weapon>,
+    mut secondaries: Query<&mut SecondaryWeapon>,
+    time: Res<Time>,
+) {
     for mut weapon in &mut query {
-        weapon.ready_timer.tick(time.delta());
+        weapon.tick(time.delta());
+    }
+    for mut secondary in &mut secondaries {
+        secondary.weapon.tick(time.delta());
     }
 }
 
-/// Move projectiles in a straight line.
+/// Move projectiles in a straight line, bouncing off the sides if they can.
+///
+/// Projectiles disappear offscreen, or when their lifetime or range runs out.
 fn move_projectiles(
     mut commands: Commands,
-    mut query: Query<(&Projectile, &mut Transform, Entity)>,
+    mut query: Query<(&mut Projectile, &mut Transform, &Pooled, Entity)>,
     time: Res<Time>,
 ) {
-    for (projectile, mut transform, entity) in &mut query {
+    for (mut projectile, mut transform, pooled, entity) in &mut query {
+        if !pooled.active() {
+            continue;
+        }
+        if let Some(lifetime) = &mut projectile.lifetime {
+            if lifetime.tick(time.delta()).finished() {
+                despawn_projectile(&mut commands, entity);
+                continue;
+            }
+        }
+
         // Compute distance vector
         let move_vec = projectile.velocity_vector * time.delta_secs();
         // extend to a Vec3
         let move_vec = move_vec.extend(0.0);
-        let loc = &mut transform.translation;
-        *loc += move_vec;
+        if let Some(range) = &mut projectile.range {
+            *range -= move_vec.length();
+            if *range <= 0.0 {
+                despawn_projectile(&mut commands, entity);
+                continue;
+            }
+        }
+        transform.translation += move_vec;
+
+        if projectile.bounces > 0 && transform.translation.x.abs() > 200.0 {
+            projectile.bounces -= 1;
+            projectile.velocity_vector.x = -projectile.velocity_vector.x;
+            transform.translation.x = transform.translation.x.clamp(-200.0, 200.0);
+            // Mirror the sprite to match; projectiles only turn about the z axis.
+            transform.rotation = transform.rotation.inverse();
+        }
 
         // Despawn the projectiles once they go offscreen.
+        let loc = transform.translation;
         let onscreen_x = (-205.0..205.0).contains(&loc.x);
         let onscreen_y = (-405.0..405.0).contains(&loc.y);
         if !(onscreen_x && onscreen_y) {
-            commands.entity(entity).despawn();
+            despawn_projectile(&mut commands, entity);
         }
     }
 }